@@ -0,0 +1,159 @@
+// Trains an MLP to classify IDX images, such as MNIST digits.
+//
+//     cargo run --release --example mnist --features std -- \
+//         train-images-idx3-ubyte train-labels-idx1-ubyte \
+//         [t10k-images-idx3-ubyte t10k-labels-idx1-ubyte] \
+//         [--hidden 16] [--epochs 1] [--batch 8] [--lr 0.1] [--limit 1000]
+//
+// Without arguments it trains on the small bar images in `src/testdata`.
+// Every sample builds its own `Unit` tree, and a hidden unit is cloned into
+// each output that reads it, so a sample costs about inputs x hidden x outputs
+// nodes; `--limit` keeps full-size MNIST runs short.
+use majin::data::{Dataset, InMemory, Sample};
+use majin::idx;
+use majin::metrics;
+use majin::nn::{squared_error, Activation, Mlp};
+use majin::optim::Sgd;
+use majin::trainer::{Control, Trainer};
+use std::cell::Cell;
+use std::env;
+use std::process;
+use std::time::Instant;
+
+const FIXTURES: [&str; 2] = [
+    "src/testdata/bars-images.idx3-ubyte",
+    "src/testdata/bars-labels.idx1-ubyte",
+];
+
+struct Options {
+    hidden: usize,
+    // one pass over MNIST, 30 over the fixtures by default
+    epochs: Option<usize>,
+    batch: usize,
+    learning_rate: f64,
+    limit: usize,
+}
+
+fn main() {
+    if let Err(message) = run(env::args().skip(1).collect()) {
+        eprintln!("error: {}", message);
+        process::exit(1);
+    }
+}
+
+fn run(args: Vec<String>) -> Result<(), String> {
+    let mut options = Options {
+        hidden: 16,
+        epochs: None,
+        batch: 8,
+        learning_rate: 0.1,
+        limit: 1000,
+    };
+    let mut paths = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let mut value = |flag: &str| -> Result<String, String> {
+            args.next().ok_or_else(|| format!("{} needs a value", flag))
+        };
+        match arg.as_str() {
+            "--hidden" => options.hidden = number(&value("--hidden")?)?,
+            "--epochs" => options.epochs = Some(number(&value("--epochs")?)?),
+            "--batch" => options.batch = number(&value("--batch")?)?,
+            "--lr" => options.learning_rate = number(&value("--lr")?)?,
+            "--limit" => options.limit = number(&value("--limit")?)?,
+            _ => paths.push(arg),
+        }
+    }
+    if paths.is_empty() {
+        paths = FIXTURES.iter().map(|path| path.to_string()).collect();
+        options.epochs = options.epochs.or(Some(30));
+    }
+    let (train, test) = match paths.as_slice() {
+        [images, labels] => (load(images, labels, options.limit, None)?, None),
+        [images, labels, test_images, test_labels] => {
+            let train = load(images, labels, options.limit, None)?;
+            let classes = train.get(0).map(|(_, targets)| targets.len());
+            let test = load(test_images, test_labels, options.limit, classes)?;
+            (train, Some(test))
+        }
+        _ => {
+            return Err("expected an images and a labels file, then optionally a test pair".into())
+        }
+    };
+    let (inputs, classes) = match train.get(0) {
+        Some((inputs, targets)) => (inputs.len(), targets.len()),
+        None => return Err("no training samples".into()),
+    };
+    println!(
+        "{} training samples of {} inputs, {} classes",
+        train.len(),
+        inputs,
+        classes
+    );
+
+    let mlp = Mlp::new(&[inputs, options.hidden, classes], Activation::Tanh, 1);
+    let started = Cell::new(Instant::now());
+    let mut trainer = Trainer::new(mlp, Sgd::new(options.learning_rate), squared_error)
+        .epochs(options.epochs.unwrap_or(1))
+        .batch_size(options.batch)
+        .seed(1)
+        .on_epoch_end(|mlp: &mut Mlp, _, stats| {
+            println!(
+                "epoch {}: loss {:.4}, train accuracy {:.1}%, {:.1}s",
+                stats.epoch,
+                stats.loss,
+                accuracy(mlp, train.samples()) * 100.0,
+                started.replace(Instant::now()).elapsed().as_secs_f64()
+            );
+            Control::Continue
+        });
+    trainer.fit(&train);
+    if let Some(test) = test {
+        println!(
+            "test accuracy {:.1}%",
+            accuracy(&trainer.model, test.samples()) * 100.0
+        );
+    }
+    Ok(())
+}
+
+// Targets are one-hot ±1 to match the tanh outputs. Without `classes` there is
+// one per label up to the largest seen.
+fn load(
+    images: &str,
+    labels: &str,
+    limit: usize,
+    classes: Option<usize>,
+) -> Result<InMemory, String> {
+    let images = idx::load(images).map_err(|err| err.to_string())?;
+    let labels = idx::load(labels).map_err(|err| err.to_string())?;
+    let classes = classes.unwrap_or_else(|| {
+        labels
+            .values
+            .iter()
+            .fold(0.0f64, |max, label| max.max(*label)) as usize
+            + 1
+    });
+    let dataset = idx::dataset(&images, &labels, classes, -1.0).map_err(|err| err.to_string())?;
+    let (head, _) = dataset.split_at(limit);
+    Ok(head)
+}
+
+// Share of samples whose largest output is their class.
+fn accuracy(mlp: &Mlp, samples: &[Sample]) -> f64 {
+    let predicted: Vec<usize> = samples
+        .iter()
+        .map(|(inputs, _)| metrics::class_of(&mlp.predict(inputs)))
+        .collect();
+    let actual: Vec<usize> = samples
+        .iter()
+        .map(|(_, targets)| metrics::class_of(targets))
+        .collect();
+    metrics::accuracy(&predicted, &actual)
+}
+
+fn number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid number `{}`", value))
+}
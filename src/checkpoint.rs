@@ -0,0 +1,447 @@
+// Versioned binary checkpoint for dense-layer parameters.
+//
+// All integers and parameters are little-endian:
+//   magic    4 bytes  b"MJCK"
+//   version  u16      currently 1
+//   scalar   u8       1 = f32, 2 = f64
+//   reserved u8       0
+//   layers   u32      number of layers, followed by (inputs u32, outputs u32) per layer
+//   params   u32      number of parameters, followed by the parameters themselves
+//   checksum u32      CRC-32 (IEEE) of every preceding byte
+//
+// Each layer holds `outputs` neurons of `inputs` weights followed by one bias, so
+// `params` must equal the sum of `(inputs + 1) * outputs`. Parameters are stored
+// layer by layer, neuron by neuron.
+//
+// `CheckpointReader` validates and reads a checkpoint in place, without allocating,
+// so a model can be embedded with `include_bytes!` on targets without a heap.
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::fmt;
+
+pub const MAGIC: [u8; 4] = *b"MJCK";
+pub const VERSION: u16 = 1;
+
+const HEADER_LEN: usize = 12;
+const CHECKSUM_LEN: usize = 4;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ScalarType {
+    F32 = 1,
+    F64 = 2,
+}
+
+impl ScalarType {
+    fn size(self) -> usize {
+        match self {
+            ScalarType::F32 => 4,
+            ScalarType::F64 => 8,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct LayerShape {
+    pub inputs: usize,
+    pub outputs: usize,
+}
+
+impl LayerShape {
+    pub fn new(inputs: usize, outputs: usize) -> Self {
+        LayerShape { inputs, outputs }
+    }
+
+    pub fn param_count(&self) -> usize {
+        (self.inputs + 1) * self.outputs
+    }
+
+    // `None` when the count doesn't fit in a `usize`, which a checkpoint read
+    // from untrusted bytes can claim on any target.
+    pub fn checked_param_count(&self) -> Option<usize> {
+        self.inputs.checked_add(1)?.checked_mul(self.outputs)
+    }
+}
+
+// The parameters `layers` need together.
+fn total_param_count(
+    mut layers: impl Iterator<Item = LayerShape>,
+) -> Result<usize, CheckpointError> {
+    layers
+        .try_fold(0usize, |total, layer| {
+            total.checked_add(layer.checked_param_count()?)
+        })
+        .ok_or(CheckpointError::ParamCountOverflow)
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum CheckpointError {
+    Truncated,
+    BadMagic,
+    UnsupportedVersion(u16),
+    UnknownScalarType(u8),
+    ParamCountMismatch { expected: usize, found: usize },
+    ParamCountOverflow,
+    // a layer whose inputs are not the outputs of the layer before
+    LayerMismatch { layer: usize },
+    ChecksumMismatch { expected: u32, found: u32 },
+    TrailingBytes(usize),
+}
+
+impl fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckpointError::Truncated => write!(f, "checkpoint is truncated"),
+            CheckpointError::BadMagic => write!(f, "not a majin checkpoint"),
+            CheckpointError::UnsupportedVersion(v) => {
+                write!(f, "unsupported checkpoint version {}", v)
+            }
+            CheckpointError::UnknownScalarType(t) => write!(f, "unknown scalar type {}", t),
+            CheckpointError::ParamCountMismatch { expected, found } => write!(
+                f,
+                "layer shapes need {} parameters but {} were given",
+                expected, found
+            ),
+            CheckpointError::ParamCountOverflow => {
+                write!(f, "layer shapes need more parameters than can be addressed")
+            }
+            CheckpointError::LayerMismatch { layer } => write!(
+                f,
+                "layer {} does not take the outputs of the layer before",
+                layer
+            ),
+            CheckpointError::ChecksumMismatch { expected, found } => write!(
+                f,
+                "checksum mismatch: expected {:08x}, found {:08x}",
+                expected, found
+            ),
+            CheckpointError::TrailingBytes(n) => write!(f, "{} unexpected trailing bytes", n),
+        }
+    }
+}
+
+pub struct CheckpointReader<'a> {
+    scalar_type: ScalarType,
+    layers: &'a [u8],
+    params: &'a [u8],
+}
+
+impl<'a> CheckpointReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Result<Self, CheckpointError> {
+        if bytes.len() < HEADER_LEN + CHECKSUM_LEN {
+            return Err(CheckpointError::Truncated);
+        }
+        if bytes[0..4] != MAGIC {
+            return Err(CheckpointError::BadMagic);
+        }
+        let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+        if version != VERSION {
+            return Err(CheckpointError::UnsupportedVersion(version));
+        }
+        let scalar_type = match bytes[6] {
+            1 => ScalarType::F32,
+            2 => ScalarType::F64,
+            other => return Err(CheckpointError::UnknownScalarType(other)),
+        };
+
+        let layer_count = read_u32(bytes, 8)? as usize;
+        let layers_end = layer_count
+            .checked_mul(8)
+            .and_then(|len| len.checked_add(HEADER_LEN))
+            .ok_or(CheckpointError::Truncated)?;
+        let param_count = read_u32(bytes, layers_end)? as usize;
+        let params_start = layers_end + 4;
+        let params_end = param_count
+            .checked_mul(scalar_type.size())
+            .and_then(|len| len.checked_add(params_start))
+            .ok_or(CheckpointError::Truncated)?;
+        let found = read_u32(bytes, params_end)?;
+        let total = params_end + CHECKSUM_LEN;
+        if bytes.len() > total {
+            return Err(CheckpointError::TrailingBytes(bytes.len() - total));
+        }
+        let expected = crc32(&bytes[..params_end]);
+        if expected != found {
+            return Err(CheckpointError::ChecksumMismatch { expected, found });
+        }
+
+        let reader = CheckpointReader {
+            scalar_type,
+            layers: &bytes[HEADER_LEN..layers_end],
+            params: &bytes[params_start..params_end],
+        };
+        let expected = total_param_count(reader.layers())?;
+        if expected != param_count {
+            return Err(CheckpointError::ParamCountMismatch {
+                expected,
+                found: param_count,
+            });
+        }
+        Ok(reader)
+    }
+
+    pub fn scalar_type(&self) -> ScalarType {
+        self.scalar_type
+    }
+
+    pub fn layer_count(&self) -> usize {
+        self.layers.len() / 8
+    }
+
+    pub fn layer(&self, index: usize) -> LayerShape {
+        let offset = index * 8;
+        LayerShape {
+            inputs: read_u32(self.layers, offset).unwrap() as usize,
+            outputs: read_u32(self.layers, offset + 4).unwrap() as usize,
+        }
+    }
+
+    pub fn layers(&self) -> impl Iterator<Item = LayerShape> + '_ {
+        (0..self.layer_count()).map(|index| self.layer(index))
+    }
+
+    pub fn param_count(&self) -> usize {
+        self.params.len() / self.scalar_type.size()
+    }
+
+    pub fn param(&self, index: usize) -> f64 {
+        let size = self.scalar_type.size();
+        let bytes = &self.params[index * size..(index + 1) * size];
+        match self.scalar_type {
+            ScalarType::F32 => f32::from_le_bytes(bytes.try_into().unwrap()) as f64,
+            ScalarType::F64 => f64::from_le_bytes(bytes.try_into().unwrap()),
+        }
+    }
+
+    pub fn params(&self) -> impl Iterator<Item = f64> + '_ {
+        (0..self.param_count()).map(|index| self.param(index))
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[derive(Debug, PartialEq, Clone)]
+pub struct Checkpoint {
+    pub scalar_type: ScalarType,
+    pub layers: Vec<LayerShape>,
+    pub params: Vec<f64>,
+}
+
+#[cfg(feature = "alloc")]
+impl Checkpoint {
+    pub fn new(
+        scalar_type: ScalarType,
+        layers: Vec<LayerShape>,
+        params: Vec<f64>,
+    ) -> Result<Self, CheckpointError> {
+        let expected = total_param_count(layers.iter().copied())?;
+        if expected != params.len() {
+            return Err(CheckpointError::ParamCountMismatch {
+                expected,
+                found: params.len(),
+            });
+        }
+        Ok(Checkpoint {
+            scalar_type,
+            layers,
+            params,
+        })
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CheckpointError> {
+        let reader = CheckpointReader::new(bytes)?;
+        Ok(Checkpoint {
+            scalar_type: reader.scalar_type(),
+            layers: reader.layers().collect(),
+            params: reader.params().collect(),
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let size = self.scalar_type.size();
+        let mut bytes = Vec::with_capacity(
+            HEADER_LEN + self.layers.len() * 8 + 4 + self.params.len() * size + CHECKSUM_LEN,
+        );
+        bytes.extend_from_slice(&MAGIC);
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        bytes.push(self.scalar_type as u8);
+        bytes.push(0);
+        bytes.extend_from_slice(&(self.layers.len() as u32).to_le_bytes());
+        for layer in self.layers.iter() {
+            bytes.extend_from_slice(&(layer.inputs as u32).to_le_bytes());
+            bytes.extend_from_slice(&(layer.outputs as u32).to_le_bytes());
+        }
+        bytes.extend_from_slice(&(self.params.len() as u32).to_le_bytes());
+        for param in self.params.iter() {
+            match self.scalar_type {
+                ScalarType::F32 => bytes.extend_from_slice(&(*param as f32).to_le_bytes()),
+                ScalarType::F64 => bytes.extend_from_slice(&param.to_le_bytes()),
+            }
+        }
+        let checksum = crc32(&bytes);
+        bytes.extend_from_slice(&checksum.to_le_bytes());
+        bytes
+    }
+
+    #[cfg(feature = "std")]
+    pub fn write_to<W: std::io::Write>(&self, mut writer: W) -> std::io::Result<()> {
+        writer.write_all(&self.to_bytes())
+    }
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Result<u32, CheckpointError> {
+    bytes
+        .get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or(CheckpointError::Truncated)
+}
+
+// Bitwise CRC-32 (IEEE 802.3); checkpoints are small enough to skip the table.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn checkpoint(scalar_type: ScalarType) -> Checkpoint {
+        let layers = vec![LayerShape::new(2, 3), LayerShape::new(3, 1)];
+        let params = (0..13).map(|i| i as f64 * 0.25 - 1.5).collect();
+        Checkpoint::new(scalar_type, layers, params).unwrap()
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for scalar_type in [ScalarType::F32, ScalarType::F64] {
+            let original = checkpoint(scalar_type);
+            let bytes = original.to_bytes();
+            assert_eq!(&bytes[0..4], b"MJCK");
+            assert_eq!(bytes.len(), 12 + 2 * 8 + 4 + 13 * scalar_type.size() + 4);
+            assert_eq!(Checkpoint::from_bytes(&bytes).unwrap(), original);
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_write_to() {
+        let original = checkpoint(ScalarType::F64);
+        let mut written = Vec::new();
+        original.write_to(&mut written).unwrap();
+        assert_eq!(written, original.to_bytes());
+    }
+
+    #[test]
+    fn test_param_count_overflow() {
+        let max = u32::MAX as usize;
+        let layers = vec![LayerShape::new(max, max), LayerShape::new(max, max)];
+        assert_eq!(
+            Checkpoint::new(ScalarType::F32, layers.clone(), vec![]),
+            Err(CheckpointError::ParamCountOverflow)
+        );
+        let bytes = Checkpoint {
+            scalar_type: ScalarType::F32,
+            layers,
+            params: vec![],
+        }
+        .to_bytes();
+        assert!(matches!(
+            CheckpointReader::new(&bytes),
+            Err(CheckpointError::ParamCountOverflow)
+        ));
+    }
+
+    #[test]
+    fn test_f32_rounds_parameters() {
+        let mut original = checkpoint(ScalarType::F32);
+        original.params[0] = 0.1;
+        let loaded = Checkpoint::from_bytes(&original.to_bytes()).unwrap();
+        assert_eq!(loaded.params[0], 0.1f32 as f64);
+    }
+
+    #[test]
+    fn test_reader_borrows_static_bytes() {
+        // what `include_bytes!` hands an embedded target
+        static BYTES: [u8; 40] = [
+            b'M', b'J', b'C', b'K', 1, 0, 1, 0, // magic, version, f32, reserved
+            1, 0, 0, 0, // one layer
+            2, 0, 0, 0, 1, 0, 0, 0, // 2 inputs, 1 output
+            3, 0, 0, 0, // three params
+            0, 0, 0x80, 0x3f, 0, 0, 0, 0xc0, 0, 0, 0, 0x3f, // 1.0, -2.0, 0.5
+            0, 0, 0, 0, // checksum, patched below
+        ];
+        let mut bytes = BYTES;
+        let checksum = crc32(&bytes[..36]);
+        bytes[36..].copy_from_slice(&checksum.to_le_bytes());
+        let reader = CheckpointReader::new(&bytes).unwrap();
+        assert_eq!(reader.scalar_type(), ScalarType::F32);
+        assert_eq!(
+            reader.layers().collect::<Vec<_>>(),
+            vec![LayerShape::new(2, 1)]
+        );
+        assert_eq!(reader.params().collect::<Vec<_>>(), vec![1.0, -2.0, 0.5]);
+    }
+
+    #[test]
+    fn test_rejects_corruption() {
+        let bytes = checkpoint(ScalarType::F64).to_bytes();
+
+        let mut flipped = bytes.clone();
+        flipped[40] ^= 0x01;
+        assert!(matches!(
+            CheckpointReader::new(&flipped),
+            Err(CheckpointError::ChecksumMismatch { .. })
+        ));
+
+        let mut magic = bytes.clone();
+        magic[0] = b'X';
+        assert_eq!(
+            CheckpointReader::new(&magic).err(),
+            Some(CheckpointError::BadMagic)
+        );
+
+        let mut version = bytes.clone();
+        version[4] = 9;
+        assert_eq!(
+            CheckpointReader::new(&version).err(),
+            Some(CheckpointError::UnsupportedVersion(9))
+        );
+
+        assert_eq!(
+            CheckpointReader::new(&bytes[..bytes.len() - 1]).err(),
+            Some(CheckpointError::Truncated)
+        );
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(
+            CheckpointReader::new(&trailing).err(),
+            Some(CheckpointError::TrailingBytes(1))
+        );
+    }
+
+    #[test]
+    fn test_param_count_must_match_shapes() {
+        let err = Checkpoint::new(ScalarType::F64, vec![LayerShape::new(2, 2)], vec![0.0; 5]);
+        assert_eq!(
+            err,
+            Err(CheckpointError::ParamCountMismatch {
+                expected: 6,
+                found: 5
+            })
+        );
+    }
+}
@@ -0,0 +1,291 @@
+use crate::core::{Op, Unit};
+use crate::nn::{intern, Mlp};
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum CodegenError {
+    // The interpreter computes no forward pass for this op, so there is nothing
+    // for generated code to agree with.
+    UnsupportedOp(&'static str),
+}
+
+impl fmt::Display for CodegenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CodegenError::UnsupportedOp(op) => {
+                write!(f, "no code can be generated for `{}` yet", op)
+            }
+        }
+    }
+}
+
+impl Unit {
+    // Emits a standalone `no_std` Rust function computing this graph's forward
+    // pass. Leaves labelled with one of `inputs` become `f64` parameters, in that
+    // order; every other leaf (trained weights, constants) is inlined as a literal.
+    // Identical subexpressions, which the tree representation duplicates, are
+    // computed once. Activations call `libm`, so the generated code only needs
+    // `libm` as a dependency. `name` and `inputs` are made into valid identifiers.
+    pub fn to_rust(&self, name: &str, inputs: &[&str]) -> Result<String, CodegenError> {
+        let mut generator = Generator {
+            inputs: inputs
+                .iter()
+                .map(|input| (*input, identifier(input)))
+                .collect(),
+            lines: Vec::new(),
+            cache: BTreeMap::new(),
+        };
+        let result = generator.emit(self)?;
+
+        let params: Vec<String> = inputs
+            .iter()
+            .map(|input| format!("{}: f64", identifier(input)))
+            .collect();
+        let signature = format!("{}({}) -> f64", identifier(name), params.join(", "));
+        Ok(function(&signature, &generator.lines, &result))
+    }
+}
+
+impl Mlp {
+    // Emits the forward pass as `fn name(inputs: &[f64]) -> [f64; outputs]`, the
+    // same function `predict` computes. It panics on fewer inputs than the first
+    // layer takes. Layers are generated one after the other, each reading the
+    // locals of the one before.
+    pub fn to_rust(&self, name: &str) -> Result<String, CodegenError> {
+        let mut generator = Generator {
+            inputs: BTreeMap::new(),
+            lines: Vec::new(),
+            cache: BTreeMap::new(),
+        };
+        let inputs = self.layers.first().map_or(0, |layer| layer.shape.inputs);
+        let mut operands: Vec<String> = (0..inputs).map(|i| format!("inputs[{}]", i)).collect();
+        for layer in self.layers.iter() {
+            let labels: Vec<&'static str> = (0..operands.len())
+                .map(|i| intern(format!("input{}", i)))
+                .collect();
+            let leaves: Vec<Unit> = labels.iter().map(|label| Unit::new(0.0, label)).collect();
+            generator.inputs = labels.into_iter().zip(operands).collect();
+            operands = layer
+                .forward(&leaves)
+                .iter()
+                .map(|unit| generator.emit(unit))
+                .collect::<Result<_, _>>()?;
+        }
+
+        let signature = format!(
+            "{}(inputs: &[f64]) -> [f64; {}]",
+            identifier(name),
+            operands.len()
+        );
+        let result = format!("[{}]", operands.join(", "));
+        Ok(function(&signature, &generator.lines, &result))
+    }
+}
+
+fn function(signature: &str, lines: &[String], result: &str) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "// Generated by majin. Do not edit.");
+    let _ = writeln!(out, "#[allow(clippy::all, unused_parens)]");
+    let _ = writeln!(out, "pub fn {} {{", signature);
+    for line in lines.iter() {
+        let _ = writeln!(out, "    {}", line);
+    }
+    let _ = writeln!(out, "    {}", result);
+    out.push_str("}\n");
+    out
+}
+
+struct Generator<'a> {
+    // input label -> the operand it is read from
+    inputs: BTreeMap<&'a str, String>,
+    lines: Vec<String>,
+    // expression -> name of the local already holding it
+    cache: BTreeMap<String, String>,
+}
+
+impl Generator<'_> {
+    // Returns an operand (literal, parameter or local) holding `unit`'s value.
+    fn emit(&mut self, unit: &Unit) -> Result<String, CodegenError> {
+        let expr = match &unit.op {
+            None => {
+                return Ok(match self.inputs.get(unit.label) {
+                    Some(operand) => operand.clone(),
+                    None => literal(unit.value),
+                });
+            }
+            Some(op) => {
+                let operands = unit
+                    .prev
+                    .iter()
+                    .map(|child| self.emit(child))
+                    .collect::<Result<Vec<String>, _>>()?;
+                match op {
+                    Op::Add(_) => format!("{} + {}", operands[0], operands[1]),
+                    Op::Mul(_) => format!("{} * {}", operands[0], operands[1]),
+                    Op::Tanh(_) => format!("libm::tanh({})", operands[0]),
+                    Op::Sigmoid(_) | Op::Relu(_) => {
+                        return Err(CodegenError::UnsupportedOp(op.symbol()))
+                    }
+                }
+            }
+        };
+        if let Some(local) = self.cache.get(&expr) {
+            return Ok(local.clone());
+        }
+        let local = format!("v{}", self.lines.len());
+        self.lines.push(format!("let {} = {};", local, expr));
+        self.cache.insert(expr, local.clone());
+        Ok(local)
+    }
+}
+
+fn literal(value: f64) -> String {
+    if value.is_nan() {
+        String::from("f64::NAN")
+    } else if value.is_infinite() {
+        String::from(if value > 0.0 {
+            "f64::INFINITY"
+        } else {
+            "f64::NEG_INFINITY"
+        })
+    } else if value < 0.0 {
+        // `Debug` always prints a decimal point or exponent, so this stays an f64
+        format!("({:?}f64)", value)
+    } else {
+        format!("{:?}f64", value)
+    }
+}
+
+// Labels may contain characters that are not valid in a Rust identifier.
+fn identifier(label: &str) -> String {
+    let mut name: String = label
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if name.chars().next().is_none_or(|c| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nn::Activation;
+
+    // `testdata/neuron.rs` is `neuron().to_rust("neuron_forward", &["x1", "x2"])`
+    // and `testdata/mlp.rs` is `mlp().to_rust("mlp_forward")`, checked in so that the generated code is compiled and run by these tests.
+    mod generated {
+        include!("testdata/neuron.rs");
+        include!("testdata/mlp.rs");
+    }
+
+    fn neuron() -> Unit {
+        let x1 = Unit::new(2.0f64, "x1");
+        let x2 = Unit::new(0.0f64, "x2");
+        let w1 = Unit::new(-3.0f64, "w1");
+        let w2 = Unit::new(1.0f64, "w2");
+        let b = Unit::new(6.881_373_587_019_543f64, "b");
+        let n = x1.clone() * w1 + x2 * w2 + b;
+        // the same subexpression twice, to exercise deduplication
+        (n.tanh() + x1 * Unit::constant(0.5)).tanh() * n.tanh()
+    }
+
+    #[test]
+    fn test_generated_code_is_up_to_date() {
+        assert_eq!(
+            neuron().to_rust("neuron_forward", &["x1", "x2"]),
+            Ok(String::from(include_str!("testdata/neuron.rs")))
+        );
+    }
+
+    fn mlp() -> Mlp {
+        Mlp::new(&[2, 3, 2], Activation::Linear, 7)
+    }
+
+    #[test]
+    fn test_generated_code_matches_interpreter() {
+        let mut graph = neuron();
+        for (x1, x2) in [(2.0, 0.0), (-1.0, 0.5), (0.25, -3.0), (10.0, 10.0)] {
+            graph.set_leaf("x1", x1);
+            graph.set_leaf("x2", x2);
+            graph.forward();
+            assert_eq!(generated::neuron_forward(x1, x2), graph.value);
+        }
+    }
+
+    #[test]
+    fn test_generated_mlp_is_up_to_date() {
+        assert_eq!(
+            mlp().to_rust("mlp_forward"),
+            Ok(String::from(include_str!("testdata/mlp.rs")))
+        );
+    }
+
+    #[test]
+    fn test_generated_mlp_matches_predict() {
+        let mlp = mlp();
+        for inputs in [[2.0, 0.0], [-1.0, 0.5], [0.25, -3.0], [10.0, 10.0]] {
+            assert_eq!(
+                generated::mlp_forward(&inputs).to_vec(),
+                mlp.predict(&inputs)
+            );
+        }
+    }
+
+    #[test]
+    fn test_rejects_ops_without_a_forward_pass() {
+        let x = Unit::new(1.0f64, "x");
+        let relu = Unit::with_child(0.0, [x].into_iter().collect(), Op::Relu('r'), "r");
+        assert_eq!(
+            (relu * Unit::constant(2.0)).to_rust("f", &["x"]),
+            Err(CodegenError::UnsupportedOp("relu"))
+        );
+    }
+
+    #[test]
+    fn test_name_is_an_identifier() {
+        let code = Unit::new(1.0f64, "x").to_rust("my model", &["x"]).unwrap();
+        assert!(code.contains("pub fn my_model(x: f64) -> f64 {"));
+    }
+
+    #[test]
+    fn test_deduplicates_subexpressions() {
+        let code = neuron().to_rust("f", &["x1", "x2"]).unwrap();
+        assert_eq!(code.matches("libm::tanh(v3)").count(), 1);
+    }
+
+    #[test]
+    fn test_literals_and_identifiers() {
+        assert_eq!(literal(1.0), "1.0f64");
+        assert_eq!(literal(-0.5), "(-0.5f64)");
+        assert_eq!(literal(1e-7), "1e-7f64");
+        assert_eq!(literal(f64::NEG_INFINITY), "f64::NEG_INFINITY");
+        assert_eq!(identifier("x.0"), "x_0");
+        assert_eq!(identifier("0x"), "_0x");
+    }
+
+    #[test]
+    fn test_unlisted_leaves_are_inlined() {
+        let graph = Unit::new(2.0f64, "w") * Unit::new(3.0f64, "x");
+        assert_eq!(
+            graph.to_rust("f", &["x"]).unwrap(),
+            "// Generated by majin. Do not edit.\n\
+             #[allow(clippy::all, unused_parens)]\n\
+             pub fn f(x: f64) -> f64 {\n    \
+             let v0 = 2.0f64 * x;\n    \
+             v0\n\
+             }\n"
+        );
+    }
+}
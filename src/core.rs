@@ -1,8 +1,12 @@
 use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
 use arrayvec::ArrayVec;
 use core::ops::{Add, Mul};
 use libm::tanh;
 
+// Label given to literal constants; they are never differentiated.
+pub const CONSTANT: &str = "const";
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct Unit {
     pub value: f64,
@@ -23,6 +27,14 @@ impl Unit {
         }
     }
 
+    pub fn constant(value: f64) -> Self {
+        Unit::new(value, CONSTANT)
+    }
+
+    pub fn is_constant(&self) -> bool {
+        self.op.is_none() && self.label == CONSTANT
+    }
+
     pub fn with_child(
         value: f64,
         children: ArrayVec<Unit, 2>,
@@ -49,25 +61,35 @@ impl Unit {
         Unit::with_child(value, children, Op::Tanh('t'), "tanh")
     }
 
-    pub fn backward(&mut self) {
+    // d(self)/d(prev[i]) for every input, the local rules `backward` chains with
+    // `self.grad`.
+    pub fn local_grads(&self) -> ArrayVec<f64, 2> {
+        let mut grads = ArrayVec::new();
         match self.op {
             Some(Op::Add(_)) => {
                 // f(x) = x + y => df/dx = 1, df/dy = 1
-                self.prev[0].grad = 1.0 * self.grad;
-                self.prev[1].grad = 1.0 * self.grad;
+                grads.push(1.0);
+                grads.push(1.0);
             }
             Some(Op::Mul(_)) => {
                 // f(x) = x * y => df/dx = y, df/dy = x
-                self.prev[0].grad = self.prev[1].value * self.grad;
-                self.prev[1].grad = self.prev[0].value * self.grad;
+                grads.push(self.prev[1].value);
+                grads.push(self.prev[0].value);
             }
             Some(Op::Tanh(_)) => {
-                // tanh'(x) = 1 - tanh^2(x)
-                let t = tanh(self.value);
-                self.prev[0].grad = (1.0 - t * t) * self.grad;
+                // tanh'(x) = 1 - tanh^2(x), and the value already is tanh(x)
+                grads.push(1.0 - self.value * self.value);
             }
             _ => {}
         }
+        grads
+    }
+
+    pub fn backward(&mut self) {
+        let grads = self.local_grads();
+        for (child, local) in self.prev.iter_mut().zip(grads) {
+            child.grad = local * self.grad;
+        }
     }
 
     pub fn traverse_backward(&mut self) {
@@ -77,6 +99,53 @@ impl Unit {
             }
     }
 
+    // Recomputes every value from the leaves up, e.g. after `set_leaf`.
+    pub fn forward(&mut self) {
+        for child in self.prev.iter_mut() {
+            child.forward();
+        }
+        match self.op {
+            Some(Op::Add(_)) => self.value = self.prev[0].value + self.prev[1].value,
+            Some(Op::Mul(_)) => self.value = self.prev[0].value * self.prev[1].value,
+            Some(Op::Tanh(_)) => self.value = tanh(self.prev[0].value),
+            _ => {}
+        }
+    }
+
+    // Sums the grads of every non-constant leaf, keyed by label, since a variable
+    // used more than once appears as several leaves in the tree.
+    pub fn leaf_grads(&self) -> BTreeMap<&'static str, f64> {
+        fn collect(unit: &Unit, grads: &mut BTreeMap<&'static str, f64>) {
+            if unit.prev.is_empty() {
+                if !unit.is_constant() {
+                    *grads.entry(unit.label).or_insert(0.0) += unit.grad;
+                }
+                return;
+            }
+            for child in unit.prev.iter() {
+                collect(child, grads);
+            }
+        }
+        let mut grads = BTreeMap::new();
+        collect(self, &mut grads);
+        grads
+    }
+
+    // Sets the value of every leaf labelled `label` and returns how many were found.
+    pub fn set_leaf(&mut self, label: &str, value: f64) -> usize {
+        if self.prev.is_empty() {
+            if self.label == label && !self.is_constant() {
+                self.value = value;
+                return 1;
+            }
+            return 0;
+        }
+        self.prev
+            .iter_mut()
+            .map(|child| child.set_leaf(label, value))
+            .sum()
+    }
+
 }
 
 impl Add for Unit {
@@ -112,6 +181,18 @@ pub enum Op {
     Relu(char),
 }
 
+impl Op {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Op::Add(_) => "+",
+            Op::Mul(_) => "*",
+            Op::Tanh(_) => "tanh",
+            Op::Sigmoid(_) => "sigmoid",
+            Op::Relu(_) => "relu",
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -210,21 +291,21 @@ mod tests {
         let mut result = intermediate3.tanh(); // 0.1305
         result.label = "result";
         result.grad = 1.0;
-        result.backward(); //  (1 - tanh^2(0.1305)) * 1 = 0.999993
+        result.backward(); //  (1 - 0.1305^2) * 1 = 0.982969
         assert!((result.prev[0].value - 0.13125).abs() < tolerance);
         assert_eq!(result.grad, 1.0);
-        assert!((result.prev[0].grad - 0.983161).abs() < tolerance);
+        assert!((result.prev[0].grad - 0.982969).abs() < tolerance);
         result.prev[0].backward();
         assert_eq!(result.prev[0].prev[0].value, 0.375); // a * b
         assert_eq!(result.prev[0].prev[1].value, 0.35); // c + d
-        assert!((result.prev[0].prev[0].grad - 0.344106).abs() < tolerance); // a * b grad
-        assert!((result.prev[0].prev[1].grad - 0.368685).abs() < tolerance); // c + d grad
+        assert!((result.prev[0].prev[0].grad - 0.344039).abs() < tolerance); // a * b grad
+        assert!((result.prev[0].prev[1].grad - 0.368614).abs() < tolerance); // c + d grad
         result.prev[0].prev[0].backward();
         result.prev[0].prev[1].backward();
-        assert!((result.prev[0].prev[0].prev[0].grad - 0.25808).abs() < tolerance); // a grad
-        assert!((result.prev[0].prev[0].prev[1].grad - 0.172053).abs() < tolerance); // b grad
-        assert!((result.prev[0].prev[1].prev[0].grad - 0.368685).abs() < tolerance); // c grad
-        assert!((result.prev[0].prev[1].prev[1].grad - 0.368685).abs() < tolerance); // d grad
+        assert!((result.prev[0].prev[0].prev[0].grad - 0.258029).abs() < tolerance); // a grad
+        assert!((result.prev[0].prev[0].prev[1].grad - 0.172020).abs() < tolerance); // b grad
+        assert!((result.prev[0].prev[1].prev[0].grad - 0.368614).abs() < tolerance); // c grad
+        assert!((result.prev[0].prev[1].prev[1].grad - 0.368614).abs() < tolerance); // d grad
     }
 
     #[test]
@@ -244,18 +325,67 @@ mod tests {
         
         assert!((result.prev[0].value - 0.13125).abs() < tolerance);
         assert_eq!(result.grad, 1.0);
-        assert!((result.prev[0].grad - 0.983161).abs() < tolerance);
+        assert!((result.prev[0].grad - 0.982969).abs() < tolerance);
         
         assert_eq!(result.prev[0].prev[0].value, 0.375); // a * b
         assert_eq!(result.prev[0].prev[1].value, 0.35);  // c + d
-        assert!((result.prev[0].prev[0].grad - 0.344106).abs() < tolerance); // a * b grad
-        assert!((result.prev[0].prev[1].grad - 0.368685).abs() < tolerance); // c + d grad
+        assert!((result.prev[0].prev[0].grad - 0.344039).abs() < tolerance); // a * b grad
+        assert!((result.prev[0].prev[1].grad - 0.368614).abs() < tolerance); // c + d grad
        
         
-        assert!((result.prev[0].prev[0].prev[0].grad - 0.25808).abs() < tolerance);  // a grad
-        assert!((result.prev[0].prev[0].prev[1].grad - 0.172053).abs() < tolerance); // b grad
-        assert!((result.prev[0].prev[1].prev[0].grad - 0.368685).abs() < tolerance); // c grad
-        assert!((result.prev[0].prev[1].prev[1].grad - 0.368685).abs() < tolerance); // d grad
+        assert!((result.prev[0].prev[0].prev[0].grad - 0.258029).abs() < tolerance);  // a grad
+        assert!((result.prev[0].prev[0].prev[1].grad - 0.172020).abs() < tolerance); // b grad
+        assert!((result.prev[0].prev[1].prev[0].grad - 0.368614).abs() < tolerance); // c grad
+        assert!((result.prev[0].prev[1].prev[1].grad - 0.368614).abs() < tolerance); // d grad
+    }
+
+    #[test]
+    fn test_forward_after_set_leaf() {
+        let a = Unit::new(2.0f64, "a");
+        let b = Unit::new(-3.0f64, "b");
+        let c = Unit::new(10.0f64, "c");
+        let mut result = a.clone() * b + c + a;
+        assert_eq!(result.value, 6.0);
+        assert_eq!(result.set_leaf("a", 1.0), 2);
+        result.forward();
+        assert_eq!(result.value, 8.0);
+        assert_eq!(result.set_leaf("missing", 1.0), 0);
+    }
+
+    #[test]
+    fn test_local_grads() {
+        let a = Unit::new(2.0f64, "a");
+        let b = Unit::new(-3.0f64, "b");
+        assert_eq!((a.clone() + b.clone()).local_grads().as_slice(), &[1.0, 1.0]);
+        assert_eq!((a.clone() * b).local_grads().as_slice(), &[-3.0, 2.0]);
+        assert_eq!(a.local_grads().len(), 0);
+        // tanh'(0.5) = 1 - tanh^2(0.5)
+        let t = Unit::new(0.5f64, "x").tanh();
+        assert!((t.local_grads()[0] - 0.786448).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_leaf_grads() {
+        let a = Unit::new(2.0f64, "a");
+        let b = Unit::new(-3.0f64, "b");
+        let mut result = a.clone() * b + a * Unit::constant(4.0);
+        result.grad = 1.0;
+        result.traverse_backward();
+        let grads = result.leaf_grads();
+        assert_eq!(grads.len(), 2);
+        assert_eq!(grads["a"], 1.0);
+        assert_eq!(grads["b"], 2.0);
+    }
+
+    #[test]
+    fn test_constant() {
+        let k = Unit::constant(3.0f64);
+        assert!(k.is_constant());
+        assert!(!Unit::new(3.0f64, "k").is_constant());
+        let mut result = k * Unit::new(2.0f64, CONSTANT);
+        assert_eq!(result.set_leaf(CONSTANT, 1.0), 0);
+        result.forward();
+        assert_eq!(result.value, 6.0);
     }
 
     #[test]
@@ -0,0 +1,426 @@
+// Loads tabular data from CSV files into an `InMemory` dataset.
+//
+// Fields are separated by `delimiter` and may be wrapped in double quotes to
+// hold it, with `""` for a literal quote; quoted fields cannot span lines.
+// Blank lines are skipped. Features must be numbers. Labels are numbers too,
+// unless `one_hot` is set: then every distinct value of a label column becomes
+// one target entry, in sorted order, so the encoding doesn't depend on row order.
+use crate::data::{InMemory, Sample, Standardizer};
+use alloc::borrow::ToOwned;
+use alloc::collections::BTreeSet;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Column {
+    Name(String),
+    Index(usize),
+}
+
+impl Column {
+    // A number is read as a zero-based index, anything else as a header name.
+    pub fn parse(text: &str) -> Column {
+        match text.trim().parse() {
+            Ok(index) => Column::Index(index),
+            Err(_) => Column::Name(text.trim().to_owned()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvOptions {
+    pub delimiter: char,
+    pub has_header: bool,
+    // `None` takes every column that isn't a label
+    pub features: Option<Vec<Column>>,
+    // empty takes the last column
+    pub labels: Vec<Column>,
+    pub one_hot: bool,
+    // the value of one-hot entries that aren't hot; -1 suits tanh outputs
+    pub cold: f64,
+    // rescale every feature to zero mean and unit variance over the whole file
+    pub standardize: bool,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        CsvOptions {
+            delimiter: ',',
+            has_header: true,
+            features: None,
+            labels: Vec::new(),
+            one_hot: false,
+            cold: 0.0,
+            standardize: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvData {
+    pub dataset: InMemory,
+    // one name per input, from the header or `column <index>`
+    pub features: Vec<String>,
+    // one name per target entry, `<label>=<value>` when one-hot encoded
+    pub targets: Vec<String>,
+    // kept to scale new inputs the same way
+    pub standardizer: Option<Standardizer>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CsvError {
+    Io(String),
+    Empty,
+    UnknownColumn(String),
+    ColumnOutOfRange {
+        index: usize,
+        columns: usize,
+    },
+    FieldCount {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    InvalidNumber {
+        line: usize,
+        column: String,
+        value: String,
+    },
+    UnterminatedQuote {
+        line: usize,
+    },
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CsvError::Io(message) => write!(f, "{}", message),
+            CsvError::Empty => write!(f, "the file has no rows"),
+            CsvError::UnknownColumn(name) => write!(f, "unknown column `{}`", name),
+            CsvError::ColumnOutOfRange { index, columns } => write!(
+                f,
+                "column {} is out of range, rows have {} columns",
+                index, columns
+            ),
+            CsvError::FieldCount {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "line {}: expected {} fields, found {}",
+                line, expected, found
+            ),
+            CsvError::InvalidNumber {
+                line,
+                column,
+                value,
+            } => write!(
+                f,
+                "line {}: `{}` in column `{}` is not a number",
+                line, value, column
+            ),
+            CsvError::UnterminatedQuote { line } => write!(f, "line {}: unterminated quote", line),
+        }
+    }
+}
+
+pub fn load(path: impl AsRef<Path>, options: &CsvOptions) -> Result<CsvData, CsvError> {
+    let path = path.as_ref();
+    let text = std::fs::read_to_string(path)
+        .map_err(|err| CsvError::Io(format!("{}: {}", path.display(), err)))?;
+    read(&text, options)
+}
+
+pub fn read(text: &str, options: &CsvOptions) -> Result<CsvData, CsvError> {
+    let mut rows = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        if !line.trim().is_empty() {
+            rows.push((index + 1, split_fields(line, options.delimiter, index + 1)?));
+        }
+    }
+    let header = match options.has_header && !rows.is_empty() {
+        true => Some(rows.remove(0).1),
+        false => None,
+    };
+    let width = match (&header, rows.first()) {
+        (_, None) => return Err(CsvError::Empty),
+        (Some(header), _) => header.len(),
+        (None, Some((_, fields))) => fields.len(),
+    };
+    let names: Vec<String> = match &header {
+        Some(header) => header.clone(),
+        None => (0..width)
+            .map(|index| format!("column {}", index))
+            .collect(),
+    };
+    let resolve = |column: &Column| match column {
+        Column::Index(index) if *index < width => Ok(*index),
+        Column::Index(index) => Err(CsvError::ColumnOutOfRange {
+            index: *index,
+            columns: width,
+        }),
+        Column::Name(name) => header
+            .as_ref()
+            .and_then(|header| header.iter().position(|field| field == name))
+            .ok_or_else(|| CsvError::UnknownColumn(name.clone())),
+    };
+    let labels = match options.labels.is_empty() {
+        true => vec![width - 1],
+        false => options
+            .labels
+            .iter()
+            .map(resolve)
+            .collect::<Result<Vec<_>, _>>()?,
+    };
+    let features = match &options.features {
+        Some(columns) => columns.iter().map(resolve).collect::<Result<Vec<_>, _>>()?,
+        None => (0..width).filter(|index| !labels.contains(index)).collect(),
+    };
+    for (line, fields) in rows.iter() {
+        if fields.len() != width {
+            return Err(CsvError::FieldCount {
+                line: *line,
+                expected: width,
+                found: fields.len(),
+            });
+        }
+    }
+
+    let classes: Vec<Vec<&str>> = match options.one_hot {
+        true => labels
+            .iter()
+            .map(|label| {
+                let values: BTreeSet<&str> = rows
+                    .iter()
+                    .map(|(_, fields)| fields[*label].as_str())
+                    .collect();
+                values.into_iter().collect()
+            })
+            .collect(),
+        false => Vec::new(),
+    };
+    let number = |line: usize, column: usize, value: &str| {
+        value.parse::<f64>().map_err(|_| CsvError::InvalidNumber {
+            line,
+            column: names[column].clone(),
+            value: value.to_owned(),
+        })
+    };
+    let mut samples: Vec<Sample> = Vec::with_capacity(rows.len());
+    for (line, fields) in rows.iter() {
+        let inputs = features
+            .iter()
+            .map(|column| number(*line, *column, &fields[*column]))
+            .collect::<Result<Vec<_>, _>>()?;
+        let targets = match options.one_hot {
+            true => {
+                let mut targets = Vec::new();
+                for (label, values) in labels.iter().zip(classes.iter()) {
+                    for value in values.iter() {
+                        targets.push(match *value == fields[*label] {
+                            true => 1.0,
+                            false => options.cold,
+                        });
+                    }
+                }
+                targets
+            }
+            false => labels
+                .iter()
+                .map(|column| number(*line, *column, &fields[*column]))
+                .collect::<Result<Vec<_>, _>>()?,
+        };
+        samples.push((inputs, targets));
+    }
+
+    let mut dataset = InMemory::new(samples);
+    let standardizer = options.standardize.then(|| {
+        let standardizer = Standardizer::fit(&dataset);
+        standardizer.apply_all(&mut dataset);
+        standardizer
+    });
+    let mut targets = Vec::new();
+    for (index, label) in labels.iter().enumerate() {
+        match classes.get(index) {
+            Some(values) => {
+                for value in values.iter() {
+                    targets.push(format!("{}={}", names[*label], value));
+                }
+            }
+            None => targets.push(names[*label].clone()),
+        }
+    }
+    Ok(CsvData {
+        dataset,
+        features: features.iter().map(|index| names[*index].clone()).collect(),
+        targets,
+        standardizer,
+    })
+}
+
+// Splits one line into trimmed fields.
+fn split_fields(line: &str, delimiter: char, number: usize) -> Result<Vec<String>, CsvError> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => quoted = false,
+                _ => field.push(c),
+            }
+        } else if c == '"' && field.trim().is_empty() {
+            field.clear();
+            quoted = true;
+        } else if c == delimiter {
+            fields.push(field.trim().to_string());
+            field.clear();
+        } else {
+            field.push(c);
+        }
+    }
+    if quoted {
+        return Err(CsvError::UnterminatedQuote { line: number });
+    }
+    fields.push(field.trim().to_string());
+    Ok(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::Dataset;
+
+    const IRIS: &str = "\
+sepal,petal,species
+5.1,1.4,setosa
+
+7.0,4.7,versicolor
+6.3,6.0,virginica
+4.9,1.5,setosa
+";
+
+    fn labelled(labels: &[&str]) -> CsvOptions {
+        CsvOptions {
+            labels: labels.iter().map(|label| Column::parse(label)).collect(),
+            ..CsvOptions::default()
+        }
+    }
+
+    #[test]
+    fn test_read_numeric_labels() {
+        let text = "x,y,z\n1,2,3\n4,5,6\n";
+        let data = read(text, &CsvOptions::default()).unwrap();
+        assert_eq!(data.features, vec!["x", "y"]);
+        assert_eq!(data.targets, vec!["z"]);
+        assert_eq!(
+            data.dataset.samples(),
+            &[(vec![1.0, 2.0], vec![3.0]), (vec![4.0, 5.0], vec![6.0])]
+        );
+        // columns by index and by name, in the order given
+        let options = CsvOptions {
+            features: Some(vec![Column::parse("z"), Column::parse("0")]),
+            ..labelled(&["y"])
+        };
+        let data = read(text, &options).unwrap();
+        assert_eq!(data.features, vec!["z", "x"]);
+        assert_eq!(data.dataset.get(1), Some((vec![6.0, 4.0], vec![5.0])));
+    }
+
+    #[test]
+    fn test_one_hot_and_standardize() {
+        let options = CsvOptions {
+            one_hot: true,
+            cold: -1.0,
+            standardize: true,
+            ..labelled(&["species"])
+        };
+        let data = read(IRIS, &options).unwrap();
+        assert_eq!(
+            data.targets,
+            vec!["species=setosa", "species=versicolor", "species=virginica"]
+        );
+        assert_eq!(data.dataset.len(), 4);
+        assert_eq!(data.dataset.samples()[1].1, vec![-1.0, 1.0, -1.0]);
+        assert_eq!(data.dataset.samples()[3].1, vec![1.0, -1.0, -1.0]);
+        let standardizer = data.standardizer.unwrap();
+        assert!((standardizer.means[0] - 5.825).abs() < 1e-9);
+        let sepal: f64 = data.dataset.samples().iter().map(|(x, _)| x[0]).sum();
+        assert!(sepal.abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_quotes_and_headerless_files() {
+        let options = CsvOptions {
+            delimiter: ';',
+            has_header: false,
+            one_hot: true,
+            ..labelled(&["0"])
+        };
+        let data = read("\"a;\"\"b\"\"\"; 1\n \"c\" ;2\n", &options).unwrap();
+        assert_eq!(data.features, vec!["column 1"]);
+        assert_eq!(data.targets, vec!["column 0=a;\"b\"", "column 0=c"]);
+        assert_eq!(data.dataset.get(1), Some((vec![2.0], vec![0.0, 1.0])));
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = |text: &str, options: &CsvOptions| read(text, options).unwrap_err();
+        let defaults = &CsvOptions::default();
+        assert_eq!(error("", defaults), CsvError::Empty);
+        assert_eq!(error("a,b\n", defaults), CsvError::Empty);
+        assert_eq!(
+            error("a,b\n1,2\n\n3\n", defaults),
+            CsvError::FieldCount {
+                line: 4,
+                expected: 2,
+                found: 1
+            }
+        );
+        let invalid = error("a,b\n1,2\nx,3\n", defaults);
+        assert_eq!(
+            invalid.to_string(),
+            "line 3: `x` in column `a` is not a number"
+        );
+        assert_eq!(
+            error("a,b\n1,\"2\n", defaults),
+            CsvError::UnterminatedQuote { line: 2 }
+        );
+        assert_eq!(
+            error(IRIS, &labelled(&["colour"])),
+            CsvError::UnknownColumn("colour".to_owned())
+        );
+        assert_eq!(
+            error(IRIS, &labelled(&["3"])).to_string(),
+            "column 3 is out of range, rows have 3 columns"
+        );
+        // text labels need one-hot encoding
+        assert!(matches!(
+            error(IRIS, &labelled(&["species"])),
+            CsvError::InvalidNumber { line: 2, .. }
+        ));
+    }
+
+    #[test]
+    fn test_load() {
+        let path = std::env::temp_dir().join(format!("majin-csv-{}.csv", std::process::id()));
+        std::fs::write(&path, "x,y\n1,2\n").unwrap();
+        let data = load(&path, &CsvOptions::default());
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(data.unwrap().dataset.samples(), &[(vec![1.0], vec![2.0])]);
+        assert!(matches!(
+            load(&path, &CsvOptions::default()),
+            Err(CsvError::Io(_))
+        ));
+    }
+}
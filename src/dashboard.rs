@@ -0,0 +1,321 @@
+use crate::train::{self, EpochStats, Options};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use majin::nn::Mlp;
+use majin::viz::{restore_terminal, setup_terminal};
+use ratatui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Bar, BarChart, BarGroup, Block, Borders, Paragraph, Sparkline},
+    Frame, Terminal,
+};
+use std::io;
+use std::time::Duration;
+
+// Loss values are scaled to integers for the sparkline and grad norms for the
+// bar chart; this keeps three decimals of resolution.
+const SCALE: f64 = 1000.0;
+
+pub fn run(
+    mlp: &mut Mlp,
+    samples: &[(Vec<f64>, Vec<f64>)],
+    options: &Options,
+) -> Result<(), io::Error> {
+    let mut terminal = setup_terminal()?;
+    let result = watch(&mut terminal, mlp, samples, options);
+    // restore the terminal even if drawing or reading events failed
+    restore_terminal(&mut terminal)?;
+    result
+}
+
+fn watch<B: Backend>(
+    terminal: &mut Terminal<B>,
+    mlp: &mut Mlp,
+    samples: &[(Vec<f64>, Vec<f64>)],
+    options: &Options,
+) -> Result<(), io::Error> {
+    let mut dashboard = Dashboard::new(options.epochs);
+    let mut result = Ok(());
+    train::train(mlp, samples, options, &mut |mlp, stats| {
+        dashboard.record(stats);
+        let step = terminal
+            .draw(|f| draw(f, &dashboard, mlp, samples))
+            .and_then(|_| quit_requested(Duration::ZERO));
+        match step {
+            Ok(quit) => !quit,
+            Err(err) => {
+                result = Err(err);
+                false
+            }
+        }
+    });
+    result?;
+    // keep the final state on screen until the user leaves
+    dashboard.finished = true;
+    loop {
+        terminal.draw(|f| draw(f, &dashboard, mlp, samples))?;
+        if quit_requested(Duration::from_millis(250))? {
+            return Ok(());
+        }
+    }
+}
+
+fn quit_requested(timeout: Duration) -> Result<bool, io::Error> {
+    if !event::poll(timeout)? {
+        return Ok(false);
+    }
+    Ok(matches!(
+        event::read()?,
+        Event::Key(key) if key.kind == KeyEventKind::Press
+            && matches!(key.code, KeyCode::Char('q') | KeyCode::Esc)
+    ))
+}
+
+struct Dashboard {
+    epochs: usize,
+    losses: Vec<f64>,
+    last: Option<EpochStats>,
+    finished: bool,
+}
+
+impl Dashboard {
+    fn new(epochs: usize) -> Self {
+        Dashboard {
+            epochs,
+            losses: Vec::new(),
+            last: None,
+            finished: false,
+        }
+    }
+
+    fn record(&mut self, stats: &EpochStats) {
+        self.losses.push(stats.loss);
+        self.last = Some(stats.clone());
+    }
+}
+
+fn draw(f: &mut Frame, dashboard: &Dashboard, mlp: &Mlp, samples: &[(Vec<f64>, Vec<f64>)]) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(8),
+            Constraint::Min(6),
+            Constraint::Length(1),
+        ])
+        .split(f.area());
+    let top = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Min(20), Constraint::Length(24)])
+        .split(rows[0]);
+    let bottom = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+        .split(rows[1]);
+
+    draw_loss(f, dashboard, top[0]);
+    f.render_widget(stats(dashboard), top[1]);
+    draw_grad_norms(f, dashboard, bottom[0]);
+    f.render_widget(decision_boundary(mlp, samples, bottom[1]), bottom[1]);
+    let help = if dashboard.finished {
+        "training finished  q quit"
+    } else {
+        "training…  q stop"
+    };
+    f.render_widget(
+        Paragraph::new(help).style(Style::default().add_modifier(Modifier::REVERSED)),
+        rows[2],
+    );
+}
+
+// The most recent losses that fit the width, scaled to the largest of them.
+fn draw_loss(f: &mut Frame, dashboard: &Dashboard, area: Rect) {
+    let width = area.width.saturating_sub(2) as usize;
+    let recent = &dashboard.losses[dashboard.losses.len().saturating_sub(width)..];
+    let data: Vec<u64> = recent
+        .iter()
+        .map(|loss| (loss.max(0.0) * SCALE) as u64)
+        .collect();
+    f.render_widget(
+        Sparkline::default()
+            .block(Block::default().title("loss").borders(Borders::ALL))
+            .data(&data)
+            .style(Style::default().fg(Color::Yellow)),
+        area,
+    );
+}
+
+fn stats(dashboard: &Dashboard) -> Paragraph<'static> {
+    let text = match dashboard.last.as_ref() {
+        Some(stats) => format!(
+            "epoch    {}/{}\nloss     {:.4}\naccuracy {:.1}%\nlr       {}",
+            stats.epoch,
+            dashboard.epochs,
+            stats.loss,
+            stats.accuracy * 100.0,
+            stats.learning_rate
+        ),
+        None => "no epochs yet".to_owned(),
+    };
+    Paragraph::new(text).block(Block::default().title("stats").borders(Borders::ALL))
+}
+
+fn draw_grad_norms(f: &mut Frame, dashboard: &Dashboard, area: Rect) {
+    let norms = dashboard
+        .last
+        .as_ref()
+        .map_or(&[][..], |stats| &stats.grad_norms[..]);
+    let bars: Vec<Bar> = norms
+        .iter()
+        .enumerate()
+        .map(|(layer, norm)| {
+            Bar::default()
+                .label(Line::from(format!("l{}", layer)))
+                .value((norm * SCALE) as u64)
+                .text_value(format!("{:.3}", norm))
+        })
+        .collect();
+    f.render_widget(
+        BarChart::default()
+            .block(
+                Block::default()
+                    .title("grad norm per layer")
+                    .borders(Borders::ALL),
+            )
+            .data(BarGroup::default().bars(&bars))
+            .bar_width(7)
+            .bar_gap(2)
+            .bar_style(Style::default().fg(Color::Cyan)),
+        area,
+    );
+}
+
+// Colours every cell of `area` by the sign of the model's first output at that
+// point and marks the samples on top. Only meaningful for two inputs.
+fn decision_boundary(
+    mlp: &Mlp,
+    samples: &[(Vec<f64>, Vec<f64>)],
+    area: Rect,
+) -> Paragraph<'static> {
+    let block = Block::default()
+        .title("decision boundary")
+        .borders(Borders::ALL);
+    if samples.iter().any(|(inputs, _)| inputs.len() != 2) {
+        return Paragraph::new("needs a dataset with two inputs").block(block);
+    }
+    let (width, height) = (
+        area.width.saturating_sub(2) as usize,
+        area.height.saturating_sub(2) as usize,
+    );
+    if width == 0 || height == 0 || samples.is_empty() {
+        return Paragraph::new("").block(block);
+    }
+    let (x_range, y_range) = (bounds(samples, 0), bounds(samples, 1));
+    let cell_of = |value: f64, (low, high): (f64, f64), cells: usize| {
+        (((value - low) / (high - low)) * (cells - 1) as f64).round() as usize
+    };
+    let mut points = vec![vec![None; width]; height];
+    for (inputs, targets) in samples.iter() {
+        let column = cell_of(inputs[0], x_range, width);
+        // the first row is the top of the plot, so flip y
+        let row = height - 1 - cell_of(inputs[1], y_range, height);
+        points[row][column] = Some(targets.first().is_some_and(|target| *target > 0.0));
+    }
+    let lines: Vec<Line> = (0..height)
+        .map(|row| {
+            let y = y_range.1 - (y_range.1 - y_range.0) * row as f64 / (height - 1).max(1) as f64;
+            let spans: Vec<Span> = (0..width)
+                .map(|column| {
+                    let x = x_range.0
+                        + (x_range.1 - x_range.0) * column as f64 / (width - 1).max(1) as f64;
+                    let positive = mlp.predict(&[x, y]).first().is_some_and(|out| *out > 0.0);
+                    let background = if positive {
+                        Color::Rgb(20, 40, 90)
+                    } else {
+                        Color::Rgb(90, 20, 20)
+                    };
+                    match points[row][column] {
+                        Some(target) => Span::styled(
+                            "●",
+                            Style::default()
+                                .bg(background)
+                                .fg(if target {
+                                    Color::LightBlue
+                                } else {
+                                    Color::LightRed
+                                })
+                                .add_modifier(Modifier::BOLD),
+                        ),
+                        None => Span::styled(" ", Style::default().bg(background)),
+                    }
+                })
+                .collect();
+            Line::from(spans)
+        })
+        .collect();
+    Paragraph::new(lines).block(block)
+}
+
+// Range of input `axis` over the samples, padded so points sit off the border.
+fn bounds(samples: &[(Vec<f64>, Vec<f64>)], axis: usize) -> (f64, f64) {
+    let (low, high) = samples
+        .iter()
+        .fold((f64::MAX, f64::MIN), |(low, high), (inputs, _)| {
+            (low.min(inputs[axis]), high.max(inputs[axis]))
+        });
+    let padding = ((high - low) * 0.1).max(0.5);
+    (low - padding, high + padding)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use majin::generate::xor_corners;
+    use ratatui::backend::TestBackend;
+
+    fn render(dashboard: &Dashboard, mlp: &Mlp, samples: &[(Vec<f64>, Vec<f64>)]) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+        terminal.draw(|f| draw(f, dashboard, mlp, samples)).unwrap();
+        let buffer = terminal.backend().buffer();
+        buffer.content().iter().map(|cell| cell.symbol()).collect()
+    }
+
+    #[test]
+    fn test_draw_after_training() {
+        let samples = xor_corners().into_samples();
+        let mut mlp = train::model(&samples, 1);
+        let options = Options {
+            epochs: 3,
+            ..Options::default()
+        };
+        let mut dashboard = Dashboard::new(options.epochs);
+        train::train(&mut mlp, &samples, &options, &mut |_, stats| {
+            dashboard.record(stats);
+            true
+        });
+        assert_eq!(dashboard.losses.len(), 3);
+        let text = render(&dashboard, &mlp, &samples);
+        assert!(text.contains("epoch    3/3"));
+        assert!(text.contains("grad norm per layer"));
+        assert!(text.contains("l0"));
+        assert!(text.contains("l1"));
+        // all four XOR corners are plotted
+        assert_eq!(text.matches('●').count(), 4);
+        assert!(text.contains("q stop"));
+    }
+
+    #[test]
+    fn test_draw_before_first_epoch_and_without_2d_inputs() {
+        let samples = vec![(vec![1.0], vec![1.0])];
+        let mlp = train::model(&samples, 1);
+        let text = render(&Dashboard::new(10), &mlp, &samples);
+        assert!(text.contains("no epochs yet"));
+        assert!(text.contains("needs a dataset with two inputs"));
+    }
+
+    #[test]
+    fn test_bounds_are_padded() {
+        let samples = xor_corners().into_samples();
+        assert_eq!(bounds(&samples, 0), (-0.5, 1.5));
+    }
+}
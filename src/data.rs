@@ -0,0 +1,289 @@
+// Datasets of (inputs, targets) samples and ways to walk over them.
+//
+// Shuffles and splits take a `rand::Rng`, so the same seed visits samples in
+// the same order everywhere. Batches come out as `Vec<Sample>`, the shape
+// `Mlp::loss` takes.
+use crate::rand::Rng;
+use alloc::vec;
+use alloc::vec::Vec;
+
+pub type Sample = (Vec<f64>, Vec<f64>);
+
+pub trait Dataset {
+    fn len(&self) -> usize;
+
+    // `None` past the end.
+    fn get(&self, index: usize) -> Option<Sample>;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Batches of up to `batch_size` samples in storage order; the last one may
+    // be short.
+    fn batches(&self, batch_size: usize) -> Batches<'_, Self>
+    where
+        Self: Sized,
+    {
+        Batches::new(self, (0..self.len()).collect(), batch_size)
+    }
+
+    // Batches over a fresh permutation of the samples, as for one epoch.
+    fn shuffled_batches(&self, batch_size: usize, rng: &mut Rng) -> Batches<'_, Self>
+    where
+        Self: Sized,
+    {
+        Batches::new(self, permutation(self.len(), rng), batch_size)
+    }
+}
+
+// A Fisher-Yates shuffle of `0..len`.
+pub fn permutation(len: usize, rng: &mut Rng) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..len).collect();
+    for index in (1..len).rev() {
+        let other = rng.below(index as u32 + 1) as usize;
+        order.swap(index, other);
+    }
+    order
+}
+
+#[derive(Debug, Clone)]
+pub struct Batches<'a, D> {
+    dataset: &'a D,
+    order: Vec<usize>,
+    batch_size: usize,
+    position: usize,
+}
+
+impl<'a, D: Dataset> Batches<'a, D> {
+    // A `batch_size` of zero is treated as one.
+    pub fn new(dataset: &'a D, order: Vec<usize>, batch_size: usize) -> Self {
+        Batches {
+            dataset,
+            order,
+            batch_size: batch_size.max(1),
+            position: 0,
+        }
+    }
+}
+
+impl<D: Dataset> Iterator for Batches<'_, D> {
+    type Item = Vec<Sample>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.position >= self.order.len() {
+            return None;
+        }
+        let end = (self.position + self.batch_size).min(self.order.len());
+        let batch = self.order[self.position..end]
+            .iter()
+            .filter_map(|index| self.dataset.get(*index))
+            .collect();
+        self.position = end;
+        Some(batch)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = self.order.len() - self.position;
+        let batches = left.div_ceil(self.batch_size);
+        (batches, Some(batches))
+    }
+}
+
+impl<D: Dataset> ExactSizeIterator for Batches<'_, D> {}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InMemory {
+    samples: Vec<Sample>,
+}
+
+impl InMemory {
+    pub fn new(samples: Vec<Sample>) -> Self {
+        InMemory { samples }
+    }
+
+    pub fn samples(&self) -> &[Sample] {
+        &self.samples
+    }
+
+    pub fn samples_mut(&mut self) -> &mut [Sample] {
+        &mut self.samples
+    }
+
+    pub fn into_samples(self) -> Vec<Sample> {
+        self.samples
+    }
+
+    pub fn shuffle(&mut self, rng: &mut Rng) {
+        let mut samples: Vec<Option<Sample>> = self.samples.drain(..).map(Some).collect();
+        self.samples = permutation(samples.len(), rng)
+            .into_iter()
+            .filter_map(|index| samples[index].take())
+            .collect();
+    }
+
+    // The first `index` samples and the rest.
+    pub fn split_at(mut self, index: usize) -> (InMemory, InMemory) {
+        let rest = self.samples.split_off(index.min(self.samples.len()));
+        (self, InMemory::new(rest))
+    }
+
+    // Shuffles, then holds out `validation` (a fraction in [0, 1]) of the
+    // samples, rounded to the nearest sample. Returns (train, validation).
+    pub fn split(mut self, validation: f64, rng: &mut Rng) -> (InMemory, InMemory) {
+        self.shuffle(rng);
+        let held_out = libm::round(self.samples.len() as f64 * validation.clamp(0.0, 1.0));
+        let train = self.samples.len() - held_out as usize;
+        self.split_at(train)
+    }
+}
+
+impl From<Vec<Sample>> for InMemory {
+    fn from(samples: Vec<Sample>) -> Self {
+        InMemory::new(samples)
+    }
+}
+
+impl Dataset for InMemory {
+    fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    fn get(&self, index: usize) -> Option<Sample> {
+        self.samples.get(index).cloned()
+    }
+}
+
+// Per-input mean and standard deviation, to rescale inputs to zero mean and
+// unit variance. Fit it on training data and apply it to everything else.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Standardizer {
+    pub means: Vec<f64>,
+    pub std_devs: Vec<f64>,
+}
+
+impl Standardizer {
+    pub fn fit<D: Dataset>(dataset: &D) -> Self {
+        let width = dataset.get(0).map_or(0, |(inputs, _)| inputs.len());
+        let (mut sums, mut squares) = (vec![0.0; width], vec![0.0; width]);
+        for index in 0..dataset.len() {
+            let Some((inputs, _)) = dataset.get(index) else {
+                continue;
+            };
+            for (column, value) in inputs.iter().take(width).enumerate() {
+                sums[column] += value;
+                squares[column] += value * value;
+            }
+        }
+        let count = dataset.len().max(1) as f64;
+        let means: Vec<f64> = sums.iter().map(|sum| sum / count).collect();
+        let std_devs = squares
+            .iter()
+            .zip(means.iter())
+            .map(|(square, mean)| libm::sqrt((square / count - mean * mean).max(0.0)))
+            .collect();
+        Standardizer { means, std_devs }
+    }
+
+    // Inputs that never vary are only centred.
+    pub fn apply(&self, inputs: &mut [f64]) {
+        for ((value, mean), std_dev) in inputs.iter_mut().zip(&self.means).zip(&self.std_devs) {
+            *value -= mean;
+            if *std_dev > 0.0 {
+                *value /= std_dev;
+            }
+        }
+    }
+
+    pub fn apply_all(&self, dataset: &mut InMemory) {
+        for (inputs, _) in dataset.samples_mut() {
+            self.apply(inputs);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numbered(count: usize) -> InMemory {
+        (0..count)
+            .map(|index| (vec![index as f64], vec![-(index as f64)]))
+            .collect::<Vec<_>>()
+            .into()
+    }
+
+    fn firsts(samples: &[Sample]) -> Vec<f64> {
+        samples.iter().map(|(inputs, _)| inputs[0]).collect()
+    }
+
+    #[test]
+    fn test_batches_in_order() {
+        let dataset = numbered(5);
+        let batches: Vec<Vec<Sample>> = dataset.batches(2).collect();
+        assert_eq!(batches.len(), 3);
+        assert_eq!(firsts(&batches[0]), vec![0.0, 1.0]);
+        assert_eq!(firsts(&batches[2]), vec![4.0]);
+        assert_eq!(batches[2][0].1, vec![-4.0]);
+        assert_eq!(dataset.batches(0).len(), 5);
+        assert_eq!(InMemory::default().batches(3).count(), 0);
+    }
+
+    #[test]
+    fn test_shuffling_is_a_reproducible_permutation() {
+        let dataset = numbered(20);
+        let order = |seed| -> Vec<f64> {
+            let mut rng = Rng::new(seed);
+            dataset
+                .shuffled_batches(3, &mut rng)
+                .flat_map(|batch| firsts(&batch))
+                .collect()
+        };
+        let first = order(1);
+        assert_eq!(first, order(1));
+        assert_ne!(first, order(2));
+        assert_ne!(first, firsts(dataset.samples()));
+        let mut sorted = first.clone();
+        sorted.sort_by(f64::total_cmp);
+        assert_eq!(sorted, firsts(dataset.samples()));
+
+        let mut shuffled = dataset.clone();
+        shuffled.shuffle(&mut Rng::new(1));
+        assert_eq!(
+            firsts(shuffled.samples()),
+            permutation(20, &mut Rng::new(1))
+                .into_iter()
+                .map(|index| index as f64)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_split() {
+        let (train, validation) = numbered(10).split(0.25, &mut Rng::new(3));
+        assert_eq!((train.len(), validation.len()), (7, 3));
+        let mut all = firsts(train.samples());
+        all.extend(firsts(validation.samples()));
+        all.sort_by(f64::total_cmp);
+        assert_eq!(all, firsts(numbered(10).samples()));
+
+        let (head, tail) = numbered(4).split_at(9);
+        assert_eq!((head.len(), tail.len()), (4, 0));
+        let (train, validation) = numbered(4).split(0.0, &mut Rng::new(3));
+        assert_eq!((train.len(), validation.len()), (4, 0));
+    }
+
+    #[test]
+    fn test_standardizer() {
+        let mut dataset = InMemory::new(vec![
+            (vec![1.0, 5.0], vec![0.0]),
+            (vec![3.0, 5.0], vec![0.0]),
+        ]);
+        let standardizer = Standardizer::fit(&dataset);
+        assert_eq!(standardizer.means, vec![2.0, 5.0]);
+        assert_eq!(standardizer.std_devs, vec![1.0, 0.0]);
+        standardizer.apply_all(&mut dataset);
+        assert_eq!(dataset.samples()[0].0, vec![-1.0, 0.0]);
+        assert_eq!(dataset.samples()[1].0, vec![1.0, 0.0]);
+    }
+}
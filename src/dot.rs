@@ -0,0 +1,128 @@
+use crate::core::{Op, Unit};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+impl Unit {
+    // Renders the graph in Graphviz DOT, in the style of micrograd's draw_dot:
+    // one record per unit showing label, value and grad, and one small node per
+    // op feeding it. Structurally equal subtrees are drawn once, like `trace`.
+    pub fn to_dot(&self) -> String {
+        let mut nodes: Vec<&Unit> = Vec::new();
+        collect(self, &mut nodes);
+
+        let mut out = String::from("digraph {\n    rankdir=LR;\n");
+        for (id, node) in nodes.iter().enumerate() {
+            let _ = writeln!(
+                out,
+                "    n{} [shape=record, label=\"{{ {} | value {:.4} | grad {:.4} }}\"];",
+                id,
+                escape(node.label),
+                node.value,
+                node.grad
+            );
+            if let Some(op) = &node.op {
+                let _ = writeln!(
+                    out,
+                    "    n{}_op [shape={}, label=\"{}\"];",
+                    id,
+                    shape(op),
+                    op.symbol()
+                );
+                let _ = writeln!(out, "    n{}_op -> n{};", id, id);
+                for child in node.prev.iter() {
+                    let child_id = position(&nodes, child);
+                    let _ = writeln!(out, "    n{} -> n{}_op;", child_id, id);
+                }
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+fn collect<'a>(node: &'a Unit, nodes: &mut Vec<&'a Unit>) {
+    if nodes.contains(&node) {
+        return;
+    }
+    nodes.push(node);
+    for child in node.prev.iter() {
+        collect(child, nodes);
+    }
+}
+
+fn position(nodes: &[&Unit], node: &Unit) -> usize {
+    nodes
+        .iter()
+        .position(|seen| *seen == node)
+        .expect("every child was collected")
+}
+
+fn shape(op: &Op) -> &'static str {
+    match op {
+        Op::Add(_) | Op::Mul(_) => "circle",
+        Op::Tanh(_) | Op::Sigmoid(_) | Op::Relu(_) => "ellipse",
+    }
+}
+
+// Characters with a meaning inside DOT record labels.
+fn escape(label: &str) -> String {
+    let mut escaped = String::with_capacity(label.len());
+    for c in label.chars() {
+        if matches!(c, '{' | '}' | '|' | '<' | '>' | '"' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_single_node() {
+        let a = Unit::new(2.0f64, "a");
+        assert_eq!(
+            a.to_dot(),
+            "digraph {\n    rankdir=LR;\n    \
+             n0 [shape=record, label=\"{ a | value 2.0000 | grad 0.0000 }\"];\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_ops_and_edges() {
+        let a = Unit::new(2.0f64, "a");
+        let b = Unit::new(-3.0f64, "b");
+        let mut root = (a * b).tanh();
+        root.label = "root";
+        root.grad = 1.0;
+        root.traverse_backward();
+        let dot = root.to_dot();
+        assert!(
+            dot.contains("n0 [shape=record, label=\"{ root | value -1.0000 | grad 1.0000 }\"];")
+        );
+        assert!(dot.contains("n0_op [shape=ellipse, label=\"tanh\"];"));
+        assert!(dot.contains("n0_op -> n0;"));
+        assert!(dot.contains("n1 -> n0_op;"));
+        assert!(dot.contains("n1_op [shape=circle, label=\"*\"];"));
+        assert!(dot.contains("n2 -> n1_op;"));
+        assert!(dot.contains("n3 -> n1_op;"));
+        assert_eq!(dot.matches("shape=record").count(), 4);
+    }
+
+    #[test]
+    fn test_repeated_leaf_drawn_once() {
+        let a = Unit::new(3.0f64, "a");
+        let dot = (a.clone() * a).to_dot();
+        assert_eq!(dot.matches("shape=record").count(), 2);
+        assert_eq!(dot.matches("n1 -> n0_op;").count(), 2);
+    }
+
+    #[test]
+    fn test_labels_are_escaped() {
+        let a = Unit::new(1.0f64, "a|b");
+        assert!(a.to_dot().contains("{ a\\|b | value"));
+    }
+}
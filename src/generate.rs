@@ -0,0 +1,254 @@
+// Deterministic toy datasets for testing classifiers and regressors.
+//
+// Every generator takes a sample count, a noise level and a seed and returns a
+// shuffled `InMemory` dataset; the same arguments give the same samples on any
+// target. Two-class sets have one target, -1 or 1, to match tanh outputs and
+// the sign-based accuracy of training; sets with more classes one-hot encode
+// them the same way. Samples are split evenly across classes, with the first
+// classes taking any remainder. `xor_corners` is the one fixed set: plain XOR,
+// the smallest problem a linear model can't fit.
+use crate::data::{InMemory, Sample};
+use crate::rand::Rng;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::f64::consts::PI;
+use libm::{cos, sin};
+
+// Two interleaving half circles, as in scikit-learn's `make_moons`. `noise` is
+// the standard deviation of Gaussian noise added to every coordinate.
+pub fn moons(samples: usize, noise: f64, seed: u64) -> InMemory {
+    let mut rng = Rng::new(seed);
+    let mut data = Vec::with_capacity(samples);
+    for class in 0..2 {
+        let count = class_size(samples, 2, class);
+        for index in 0..count {
+            let angle = PI * fraction(index, count);
+            let (x, y) = match class {
+                0 => (cos(angle), sin(angle)),
+                _ => (1.0 - cos(angle), 0.5 - sin(angle)),
+            };
+            data.push(binary(&mut rng, [x, y], noise, class == 1));
+        }
+    }
+    shuffled(data, &mut rng)
+}
+
+// A small circle, of radius `factor` (in (0, 1)), inside a unit circle; the
+// inner circle is the positive class.
+pub fn circles(samples: usize, noise: f64, factor: f64, seed: u64) -> InMemory {
+    let mut rng = Rng::new(seed);
+    let mut data = Vec::with_capacity(samples);
+    for class in 0..2 {
+        let count = class_size(samples, 2, class);
+        let radius = if class == 0 { 1.0 } else { factor };
+        for index in 0..count {
+            // the end point would repeat the start of a full turn
+            let angle = 2.0 * PI * index as f64 / count as f64;
+            let point = [radius * cos(angle), radius * sin(angle)];
+            data.push(binary(&mut rng, point, noise, class == 1));
+        }
+    }
+    shuffled(data, &mut rng)
+}
+
+// `classes` arms spiralling out of the origin, as in the CS231n spiral demo.
+// Noise perturbs the angle, in radians.
+pub fn spirals(samples: usize, classes: usize, noise: f64, seed: u64) -> InMemory {
+    let mut rng = Rng::new(seed);
+    let mut data = Vec::with_capacity(samples);
+    let classes = classes.max(2);
+    for class in 0..classes {
+        let count = class_size(samples, classes, class);
+        for index in 0..count {
+            let radius = fraction(index, count);
+            let turn = 2.0 * PI * class as f64 / classes as f64;
+            let angle = turn + 4.0 * radius + rng.normal(0.0, noise);
+            let point = [radius * cos(angle), radius * sin(angle)];
+            data.push(labelled(point.to_vec(), classes, class));
+        }
+    }
+    shuffled(data, &mut rng)
+}
+
+// Gaussian clusters of standard deviation `noise` around `classes` centres,
+// drawn uniformly from [-2, 2] x [-2, 2].
+pub fn blobs(samples: usize, classes: usize, noise: f64, seed: u64) -> InMemory {
+    let mut rng = Rng::new(seed);
+    let classes = classes.max(2);
+    let centers: Vec<[f64; 2]> = (0..classes)
+        .map(|_| [rng.uniform(-2.0, 2.0), rng.uniform(-2.0, 2.0)])
+        .collect();
+    let mut data = Vec::with_capacity(samples);
+    for (class, center) in centers.iter().enumerate() {
+        for _ in 0..class_size(samples, classes, class) {
+            let point = [rng.normal(center[0], noise), rng.normal(center[1], noise)];
+            data.push(labelled(point.to_vec(), classes, class));
+        }
+    }
+    shuffled(data, &mut rng)
+}
+
+// Points uniform in [-1, 1] x [-1, 1], positive where the coordinates have
+// different signs. Noise is added after labelling, so it blurs the boundary.
+pub fn xor(samples: usize, noise: f64, seed: u64) -> InMemory {
+    let mut rng = Rng::new(seed);
+    let data = (0..samples)
+        .map(|_| {
+            let point = [rng.uniform(-1.0, 1.0), rng.uniform(-1.0, 1.0)];
+            binary(&mut rng, point, noise, point[0] * point[1] < 0.0)
+        })
+        .collect();
+    InMemory::new(data)
+}
+
+// The four corners of the unit square, positive where exactly one input is 1,
+// in the order (0, 0), (0, 1), (1, 0), (1, 1).
+pub fn xor_corners() -> InMemory {
+    InMemory::new(vec![
+        (vec![0.0, 0.0], vec![-1.0]),
+        (vec![0.0, 1.0], vec![1.0]),
+        (vec![1.0, 0.0], vec![1.0]),
+        (vec![1.0, 1.0], vec![-1.0]),
+    ])
+}
+
+// Inputs uniform in [-1, 1] per weight, with the target `weights · x + bias`
+// plus Gaussian noise of standard deviation `noise`.
+pub fn linear(samples: usize, weights: &[f64], bias: f64, noise: f64, seed: u64) -> InMemory {
+    let mut rng = Rng::new(seed);
+    let data = (0..samples)
+        .map(|_| {
+            let inputs: Vec<f64> = weights.iter().map(|_| rng.uniform(-1.0, 1.0)).collect();
+            let target = inputs
+                .iter()
+                .zip(weights)
+                .fold(bias, |sum, (x, w)| sum + x * w);
+            (inputs, vec![target + rng.normal(0.0, noise)])
+        })
+        .collect();
+    InMemory::new(data)
+}
+
+fn class_size(samples: usize, classes: usize, class: usize) -> usize {
+    samples / classes + usize::from(class < samples % classes)
+}
+
+// `index` spread evenly over [0, 1].
+fn fraction(index: usize, count: usize) -> f64 {
+    index as f64 / count.saturating_sub(1).max(1) as f64
+}
+
+fn binary(rng: &mut Rng, point: [f64; 2], noise: f64, positive: bool) -> Sample {
+    let inputs = vec![rng.normal(point[0], noise), rng.normal(point[1], noise)];
+    (inputs, vec![if positive { 1.0 } else { -1.0 }])
+}
+
+fn labelled(inputs: Vec<f64>, classes: usize, class: usize) -> Sample {
+    let mut targets = vec![-1.0; classes];
+    targets[class] = 1.0;
+    (inputs, targets)
+}
+
+fn shuffled(data: Vec<Sample>, rng: &mut Rng) -> InMemory {
+    let mut dataset = InMemory::new(data);
+    dataset.shuffle(rng);
+    dataset
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::Dataset;
+    use libm::sqrt;
+
+    fn positives(dataset: &InMemory) -> usize {
+        dataset
+            .samples()
+            .iter()
+            .filter(|(_, targets)| targets[0] > 0.0)
+            .count()
+    }
+
+    #[test]
+    fn test_generators_are_deterministic() {
+        assert_eq!(moons(50, 0.1, 3), moons(50, 0.1, 3));
+        assert_ne!(moons(50, 0.1, 3), moons(50, 0.1, 4));
+        assert_eq!(spirals(30, 3, 0.2, 3), spirals(30, 3, 0.2, 3));
+        assert_eq!(xor(20, 0.0, 1), xor(20, 0.0, 1));
+    }
+
+    #[test]
+    fn test_moons_without_noise() {
+        let dataset = moons(41, 0.0, 1);
+        assert_eq!(dataset.len(), 41);
+        assert_eq!(positives(&dataset), 20);
+        for (inputs, targets) in dataset.samples() {
+            // each moon is a half circle of radius 1 around its own centre
+            let center = if targets[0] > 0.0 {
+                [1.0, 0.5]
+            } else {
+                [0.0, 0.0]
+            };
+            let radius = sqrt((inputs[0] - center[0]).powi(2) + (inputs[1] - center[1]).powi(2));
+            assert!((radius - 1.0).abs() < 1e-9);
+        }
+        // shuffled, so the classes are interleaved
+        let firsts: Vec<f64> = dataset.samples()[..10].iter().map(|(_, y)| y[0]).collect();
+        assert!(firsts.contains(&1.0) && firsts.contains(&-1.0));
+    }
+
+    #[test]
+    fn test_circles() {
+        let dataset = circles(20, 0.0, 0.5, 1);
+        assert_eq!(positives(&dataset), 10);
+        for (inputs, targets) in dataset.samples() {
+            let radius = sqrt(inputs[0] * inputs[0] + inputs[1] * inputs[1]);
+            let expected = if targets[0] > 0.0 { 0.5 } else { 1.0 };
+            assert!((radius - expected).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_spirals_and_blobs_one_hot() {
+        for dataset in [spirals(31, 3, 0.1, 2), blobs(31, 3, 0.3, 2)] {
+            assert_eq!(dataset.len(), 31);
+            let mut counts = [0; 3];
+            for (inputs, targets) in dataset.samples() {
+                assert_eq!(inputs.len(), 2);
+                assert_eq!(targets.iter().filter(|t| **t == 1.0).count(), 1);
+                assert_eq!(targets.iter().filter(|t| **t == -1.0).count(), 2);
+                counts[targets.iter().position(|t| *t == 1.0).unwrap()] += 1;
+            }
+            assert_eq!(counts, [11, 10, 10]);
+        }
+        for (inputs, _) in spirals(20, 2, 0.0, 1).samples() {
+            assert!(sqrt(inputs[0] * inputs[0] + inputs[1] * inputs[1]) <= 1.0 + 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_xor_labels() {
+        for (inputs, targets) in xor(100, 0.0, 5).samples() {
+            assert_eq!(targets[0] > 0.0, inputs[0] * inputs[1] < 0.0);
+            assert!(inputs.iter().all(|x| x.abs() <= 1.0));
+        }
+        for (inputs, targets) in xor_corners().samples() {
+            assert_eq!(targets[0] > 0.0, inputs[0] != inputs[1]);
+        }
+    }
+
+    #[test]
+    fn test_linear() {
+        let dataset = linear(10, &[2.0, -1.0], 0.5, 0.0, 1);
+        for (inputs, targets) in dataset.samples() {
+            assert_eq!(targets[0], 2.0 * inputs[0] - inputs[1] + 0.5);
+        }
+        let noisy = linear(2000, &[1.0], 0.0, 0.1, 1);
+        let residual: f64 = noisy
+            .samples()
+            .iter()
+            .map(|(x, y)| (y[0] - x[0]).powi(2))
+            .sum();
+        assert!((sqrt(residual / 2000.0) - 0.1).abs() < 0.01);
+    }
+}
@@ -0,0 +1,310 @@
+// Reader for the IDX format used by MNIST and Fashion-MNIST.
+//
+// A file starts with two zero bytes, a type code and the number of dimensions,
+// then one big-endian u32 per dimension and the values themselves, big-endian
+// and in row-major order. The first dimension counts items: images for an
+// `idx3` file of `count x rows x columns` bytes, labels for an `idx1` file.
+use crate::data::{InMemory, Sample};
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataType {
+    U8 = 0x08,
+    I8 = 0x09,
+    I16 = 0x0b,
+    I32 = 0x0c,
+    F32 = 0x0d,
+    F64 = 0x0e,
+}
+
+impl DataType {
+    fn from_code(code: u8) -> Option<DataType> {
+        match code {
+            0x08 => Some(DataType::U8),
+            0x09 => Some(DataType::I8),
+            0x0b => Some(DataType::I16),
+            0x0c => Some(DataType::I32),
+            0x0d => Some(DataType::F32),
+            0x0e => Some(DataType::F64),
+            _ => None,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        match self {
+            DataType::U8 | DataType::I8 => 1,
+            DataType::I16 => 2,
+            DataType::I32 | DataType::F32 => 4,
+            DataType::F64 => 8,
+        }
+    }
+
+    fn decode(&self, bytes: &[u8]) -> f64 {
+        match self {
+            DataType::U8 => bytes[0] as f64,
+            DataType::I8 => bytes[0] as i8 as f64,
+            DataType::I16 => i16::from_be_bytes([bytes[0], bytes[1]]) as f64,
+            DataType::I32 => i32::from_be_bytes(bytes.try_into().unwrap()) as f64,
+            DataType::F32 => f32::from_be_bytes(bytes.try_into().unwrap()) as f64,
+            DataType::F64 => f64::from_be_bytes(bytes.try_into().unwrap()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IdxArray {
+    pub data_type: DataType,
+    pub dims: Vec<usize>,
+    pub values: Vec<f64>,
+}
+
+impl IdxArray {
+    pub fn item_count(&self) -> usize {
+        self.dims.first().copied().unwrap_or(0)
+    }
+
+    // Values per item, 784 for a 28x28 image.
+    pub fn item_size(&self) -> usize {
+        self.dims.iter().skip(1).product()
+    }
+
+    pub fn item(&self, index: usize) -> &[f64] {
+        let size = self.item_size();
+        &self.values[index * size..(index + 1) * size]
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum IdxError {
+    Io(String),
+    Truncated,
+    BadMagic,
+    UnknownDataType(u8),
+    TrailingBytes(usize),
+    CountMismatch { images: usize, labels: usize },
+    NotLabels,
+    InvalidLabel { index: usize },
+}
+
+impl fmt::Display for IdxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IdxError::Io(message) => write!(f, "{}", message),
+            IdxError::Truncated => write!(f, "idx file is truncated"),
+            IdxError::BadMagic => write!(f, "not an idx file"),
+            IdxError::UnknownDataType(code) => write!(f, "unknown idx data type 0x{:02x}", code),
+            IdxError::TrailingBytes(n) => write!(f, "{} unexpected trailing bytes", n),
+            IdxError::CountMismatch { images, labels } => {
+                write!(f, "{} images but {} labels", images, labels)
+            }
+            IdxError::NotLabels => write!(f, "labels must be one number per item"),
+            IdxError::InvalidLabel { index } => {
+                write!(f, "label of item {} is not a known class", index)
+            }
+        }
+    }
+}
+
+pub fn parse(bytes: &[u8]) -> Result<IdxArray, IdxError> {
+    let header = bytes.get(..4).ok_or(IdxError::Truncated)?;
+    if header[..2] != [0, 0] {
+        return Err(IdxError::BadMagic);
+    }
+    let data_type = DataType::from_code(header[2]).ok_or(IdxError::UnknownDataType(header[2]))?;
+    let rank = header[3] as usize;
+    let dims: Vec<usize> = bytes
+        .get(4..4 + rank * 4)
+        .ok_or(IdxError::Truncated)?
+        .chunks_exact(4)
+        .map(|dim| u32::from_be_bytes(dim.try_into().unwrap()) as usize)
+        .collect();
+    let body = &bytes[4 + rank * 4..];
+    let len = dims
+        .iter()
+        .try_fold(data_type.size(), |len, dim| len.checked_mul(*dim))
+        .ok_or(IdxError::Truncated)?;
+    if body.len() < len {
+        return Err(IdxError::Truncated);
+    }
+    if body.len() > len {
+        return Err(IdxError::TrailingBytes(body.len() - len));
+    }
+    let values = body
+        .chunks_exact(data_type.size())
+        .map(|value| data_type.decode(value))
+        .collect();
+    Ok(IdxArray {
+        data_type,
+        dims,
+        values,
+    })
+}
+
+#[cfg(feature = "std")]
+pub fn load(path: impl AsRef<std::path::Path>) -> Result<IdxArray, IdxError> {
+    let path = path.as_ref();
+    let bytes = std::fs::read(path)
+        .map_err(|err| IdxError::Io(alloc::format!("{}: {}", path.display(), err)))?;
+    parse(&bytes)
+}
+
+// Pairs every image with its label, one-hot encoded over `classes` with `cold`
+// for the entries that aren't hot. Byte images are scaled to [0, 1].
+pub fn dataset(
+    images: &IdxArray,
+    labels: &IdxArray,
+    classes: usize,
+    cold: f64,
+) -> Result<InMemory, IdxError> {
+    if labels.item_size() != 1 {
+        return Err(IdxError::NotLabels);
+    }
+    if images.item_count() != labels.item_count() {
+        return Err(IdxError::CountMismatch {
+            images: images.item_count(),
+            labels: labels.item_count(),
+        });
+    }
+    let scale = match images.data_type {
+        DataType::U8 => 1.0 / 255.0,
+        _ => 1.0,
+    };
+    let mut samples: Vec<Sample> = Vec::with_capacity(images.item_count());
+    for (index, label) in labels.values.iter().enumerate() {
+        if *label < 0.0 || *label >= classes as f64 || label.fract() != 0.0 {
+            return Err(IdxError::InvalidLabel { index });
+        }
+        let inputs = images
+            .item(index)
+            .iter()
+            .map(|value| value * scale)
+            .collect();
+        let mut targets = vec![cold; classes];
+        targets[*label as usize] = 1.0;
+        samples.push((inputs, targets));
+    }
+    Ok(InMemory::new(samples))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::Dataset;
+    use crate::nn::{Activation, Mlp};
+
+    // 12 5x5 images of three classes, vertical, horizontal and diagonal bars,
+    // at varying positions and brightness.
+    const IMAGES: &[u8] = include_bytes!("testdata/bars-images.idx3-ubyte");
+    const LABELS: &[u8] = include_bytes!("testdata/bars-labels.idx1-ubyte");
+
+    fn encode(data_type: DataType, dims: &[u32], values: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![0, 0, data_type as u8, dims.len() as u8];
+        for dim in dims {
+            bytes.extend_from_slice(&dim.to_be_bytes());
+        }
+        bytes.extend_from_slice(values);
+        bytes
+    }
+
+    #[test]
+    fn test_parse_fixtures() {
+        let images = parse(IMAGES).unwrap();
+        assert_eq!(images.data_type, DataType::U8);
+        assert_eq!(images.dims, vec![12, 5, 5]);
+        assert_eq!((images.item_count(), images.item_size()), (12, 25));
+        let labels = parse(LABELS).unwrap();
+        assert_eq!(labels.dims, vec![12]);
+        assert_eq!(labels.values[..3], [0.0, 1.0, 2.0]);
+    }
+
+    #[test]
+    fn test_parse_wider_types() {
+        let mut values = Vec::new();
+        values.extend_from_slice(&(-2i16).to_be_bytes());
+        values.extend_from_slice(&300i16.to_be_bytes());
+        let array = parse(&encode(DataType::I16, &[2], &values)).unwrap();
+        assert_eq!(array.values, vec![-2.0, 300.0]);
+        let array = parse(&encode(DataType::F64, &[1, 1], &1.5f64.to_be_bytes())).unwrap();
+        assert_eq!(array.values, vec![1.5]);
+        assert_eq!(array.item(0), &[1.5]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(parse(&[0, 0, 8]), Err(IdxError::Truncated));
+        assert_eq!(parse(&[1, 0, 8, 1]), Err(IdxError::BadMagic));
+        assert_eq!(parse(&[0, 0, 7, 0]), Err(IdxError::UnknownDataType(7)));
+        assert_eq!(
+            parse(&encode(DataType::U8, &[3], &[1, 2])),
+            Err(IdxError::Truncated)
+        );
+        assert_eq!(
+            parse(&encode(DataType::U8, &[1], &[1, 2])),
+            Err(IdxError::TrailingBytes(1))
+        );
+        assert_eq!(parse(&IMAGES[..IMAGES.len() - 1]), Err(IdxError::Truncated));
+    }
+
+    #[test]
+    fn test_dataset() {
+        let images = parse(&encode(DataType::U8, &[2, 1, 2], &[0, 255, 51, 0])).unwrap();
+        let labels = parse(&encode(DataType::U8, &[2], &[2, 0])).unwrap();
+        let dataset = dataset(&images, &labels, 3, -1.0).unwrap();
+        assert_eq!(
+            dataset.get(0),
+            Some((vec![0.0, 1.0], vec![-1.0, -1.0, 1.0]))
+        );
+        assert_eq!(
+            dataset.get(1),
+            Some((vec![0.2, 0.0], vec![1.0, -1.0, -1.0]))
+        );
+
+        assert_eq!(
+            super::dataset(&images, &labels, 2, 0.0),
+            Err(IdxError::InvalidLabel { index: 0 })
+        );
+        let one = parse(&encode(DataType::U8, &[1], &[0])).unwrap();
+        assert_eq!(
+            super::dataset(&images, &one, 3, 0.0),
+            Err(IdxError::CountMismatch {
+                images: 2,
+                labels: 1
+            })
+        );
+        assert_eq!(
+            super::dataset(&images, &images, 3, 0.0),
+            Err(IdxError::NotLabels)
+        );
+    }
+
+    #[test]
+    fn test_mlp_learns_fixtures() {
+        let dataset = dataset(&parse(IMAGES).unwrap(), &parse(LABELS).unwrap(), 3, -1.0).unwrap();
+        let mut mlp = Mlp::new(&[25, 3], Activation::Tanh, 1);
+        for _ in 0..30 {
+            for batch in dataset.batches(4) {
+                let mut loss = mlp.loss(&batch);
+                loss.grad = 1.0;
+                loss.traverse_backward();
+                mlp.step(&loss.leaf_grads(), 0.5);
+            }
+        }
+        for (inputs, targets) in dataset.samples() {
+            let outputs = mlp.predict(inputs);
+            let predicted = (0..3)
+                .max_by(|a, b| outputs[*a].total_cmp(&outputs[*b]))
+                .unwrap();
+            assert_eq!(targets[predicted], 1.0);
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_load() {
+        let missing = load("testdata/missing-idx1-ubyte").unwrap_err();
+        assert!(matches!(missing, IdxError::Io(_)));
+    }
+}
@@ -0,0 +1,551 @@
+use crate::core::{Op, Unit, CONSTANT};
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use arrayvec::ArrayVec;
+use core::fmt::{self, Write};
+
+// Graphs are stored as nested objects mirroring `prev`:
+//   {"label":"root","value":25,"grad":0,"op":{"kind":"add","char":"+"},"prev":[...]}
+// Non-finite floats are written as the strings "NaN", "inf" and "-inf" so that a
+// failing computation can still be captured.
+impl Unit {
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        write_unit(self, &mut out);
+        out
+    }
+
+    // Labels are `&'static str`, so labels other than the ones the engine itself
+    // uses are leaked; this is meant for replaying a handful of snapshots.
+    pub fn from_json(input: &str) -> Result<Unit, JsonError> {
+        let mut reader = Reader { input, pos: 0 };
+        let value = reader.value()?;
+        reader.skip_whitespace();
+        if let Some(c) = reader.peek() {
+            return Err(reader.error(JsonErrorKind::UnexpectedChar(c)));
+        }
+        unit_from(0, &value)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct JsonError {
+    // Byte offset of the error, or of the object holding an invalid field.
+    pub position: usize,
+    pub kind: JsonErrorKind,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum JsonErrorKind {
+    UnexpectedChar(char),
+    UnexpectedEnd,
+    InvalidNumber,
+    InvalidEscape,
+    MissingField(&'static str),
+    InvalidField(&'static str),
+    UnknownOp(String),
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            JsonErrorKind::UnexpectedChar(c) => write!(f, "unexpected `{}`", c)?,
+            JsonErrorKind::UnexpectedEnd => write!(f, "unexpected end of input")?,
+            JsonErrorKind::InvalidNumber => write!(f, "invalid number")?,
+            JsonErrorKind::InvalidEscape => write!(f, "invalid string escape")?,
+            JsonErrorKind::MissingField(name) => write!(f, "missing field `{}`", name)?,
+            JsonErrorKind::InvalidField(name) => write!(f, "invalid field `{}`", name)?,
+            JsonErrorKind::UnknownOp(kind) => write!(f, "unknown op `{}`", kind)?,
+        }
+        write!(f, " at position {}", self.position)
+    }
+}
+
+fn write_unit(unit: &Unit, out: &mut String) {
+    out.push_str("{\"label\":");
+    write_string(unit.label, out);
+    out.push_str(",\"value\":");
+    write_number(unit.value, out);
+    out.push_str(",\"grad\":");
+    write_number(unit.grad, out);
+    out.push_str(",\"op\":");
+    match &unit.op {
+        Some(op) => {
+            let (kind, c) = op_parts(op);
+            out.push_str("{\"kind\":");
+            write_string(kind, out);
+            out.push_str(",\"char\":");
+            let mut buf = [0u8; 4];
+            write_string(c.encode_utf8(&mut buf), out);
+            out.push('}');
+        }
+        None => out.push_str("null"),
+    }
+    out.push_str(",\"prev\":[");
+    for (idx, child) in unit.prev.iter().enumerate() {
+        if idx > 0 {
+            out.push(',');
+        }
+        write_unit(child, out);
+    }
+    out.push_str("]}");
+}
+
+fn write_number(value: f64, out: &mut String) {
+    if value.is_nan() {
+        out.push_str("\"NaN\"");
+    } else if value.is_infinite() {
+        out.push_str(if value > 0.0 { "\"inf\"" } else { "\"-inf\"" });
+    } else {
+        // `Display` for f64 prints the shortest representation that round-trips.
+        let _ = write!(out, "{}", value);
+    }
+}
+
+fn write_string(s: &str, out: &mut String) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+fn op_parts(op: &Op) -> (&'static str, char) {
+    match op {
+        Op::Add(c) => ("add", *c),
+        Op::Mul(c) => ("mul", *c),
+        Op::Tanh(c) => ("tanh", *c),
+        Op::Sigmoid(c) => ("sigmoid", *c),
+        Op::Relu(c) => ("relu", *c),
+    }
+}
+
+// The subset of JSON needed for graphs, tagged with where each value started.
+enum Value {
+    Null,
+    Number(f64),
+    Str(String),
+    Array(Vec<(usize, Value)>),
+    Object(Vec<(String, (usize, Value))>),
+}
+
+fn unit_from(position: usize, value: &Value) -> Result<Unit, JsonError> {
+    let fields = match value {
+        Value::Object(fields) => fields,
+        _ => return Err(invalid(position, "unit")),
+    };
+    let field = |name: &'static str| {
+        fields
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value)
+            .ok_or(JsonError {
+                position,
+                kind: JsonErrorKind::MissingField(name),
+            })
+    };
+
+    let label = match field("label")? {
+        (_, Value::Str(label)) => intern(label),
+        (pos, _) => return Err(invalid(*pos, "label")),
+    };
+    let value = number_from(field("value")?, "value")?;
+    let grad = number_from(field("grad")?, "grad")?;
+    let op = match field("op")? {
+        (_, Value::Null) => None,
+        (pos, op) => Some(op_from(*pos, op)?),
+    };
+    // as many children as the op reads, so `forward` and `backward` can't
+    // index past them
+    let arity = match op {
+        None => 0,
+        Some(Op::Add(_) | Op::Mul(_)) => 2,
+        Some(Op::Tanh(_) | Op::Sigmoid(_) | Op::Relu(_)) => 1,
+    };
+    let mut prev = ArrayVec::new();
+    match field("prev")? {
+        (pos, Value::Array(children)) if children.len() == arity => {
+            for (child_pos, child) in children.iter() {
+                prev.push(Box::new(unit_from(*child_pos, child)?));
+            }
+        }
+        (pos, _) => return Err(invalid(*pos, "prev")),
+    }
+    Ok(Unit {
+        value,
+        grad,
+        prev,
+        op,
+        label,
+    })
+}
+
+fn number_from(value: &(usize, Value), name: &'static str) -> Result<f64, JsonError> {
+    match value {
+        (_, Value::Number(n)) => Ok(*n),
+        (_, Value::Str(s)) if s == "NaN" => Ok(f64::NAN),
+        (_, Value::Str(s)) if s == "inf" => Ok(f64::INFINITY),
+        (_, Value::Str(s)) if s == "-inf" => Ok(f64::NEG_INFINITY),
+        (pos, _) => Err(invalid(*pos, name)),
+    }
+}
+
+fn op_from(position: usize, value: &Value) -> Result<Op, JsonError> {
+    let fields = match value {
+        Value::Object(fields) => fields,
+        _ => return Err(invalid(position, "op")),
+    };
+    let string = |name: &'static str| match fields.iter().find(|(key, _)| key == name) {
+        Some((_, (_, Value::Str(s)))) => Ok(s),
+        Some((_, (pos, _))) => Err(invalid(*pos, name)),
+        None => Err(JsonError {
+            position,
+            kind: JsonErrorKind::MissingField(name),
+        }),
+    };
+    let kind = string("kind")?;
+    let mut chars = string("char")?.chars();
+    let c = match (chars.next(), chars.next()) {
+        (Some(c), None) => c,
+        _ => return Err(invalid(position, "char")),
+    };
+    match kind.as_str() {
+        "add" => Ok(Op::Add(c)),
+        "mul" => Ok(Op::Mul(c)),
+        "tanh" => Ok(Op::Tanh(c)),
+        "sigmoid" => Ok(Op::Sigmoid(c)),
+        "relu" => Ok(Op::Relu(c)),
+        other => Err(JsonError {
+            position,
+            kind: JsonErrorKind::UnknownOp(other.to_string()),
+        }),
+    }
+}
+
+fn intern(label: &str) -> &'static str {
+    match label {
+        "result" => "result",
+        "tanh" => "tanh",
+        CONSTANT => CONSTANT,
+        _ => Box::leak(label.to_string().into_boxed_str()),
+    }
+}
+
+fn invalid(position: usize, name: &'static str) -> JsonError {
+    JsonError {
+        position,
+        kind: JsonErrorKind::InvalidField(name),
+    }
+}
+
+struct Reader<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn value(&mut self) -> Result<Value, JsonError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => self.object(),
+            Some('[') => self.array(),
+            Some('"') => self.string().map(Value::Str),
+            Some('n') => self.keyword("null", Value::Null),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.number(),
+            Some(c) => Err(self.error(JsonErrorKind::UnexpectedChar(c))),
+            None => Err(self.error(JsonErrorKind::UnexpectedEnd)),
+        }
+    }
+
+    fn object(&mut self) -> Result<Value, JsonError> {
+        self.expect('{')?;
+        let mut fields = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.bump();
+            return Ok(Value::Object(fields));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.expect(':')?;
+            self.skip_whitespace();
+            let pos = self.pos;
+            fields.push((key, (pos, self.value()?)));
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.bump(),
+                Some('}') => {
+                    self.bump();
+                    return Ok(Value::Object(fields));
+                }
+                Some(c) => return Err(self.error(JsonErrorKind::UnexpectedChar(c))),
+                None => return Err(self.error(JsonErrorKind::UnexpectedEnd)),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, JsonError> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.bump();
+            return Ok(Value::Array(items));
+        }
+        loop {
+            self.skip_whitespace();
+            let pos = self.pos;
+            items.push((pos, self.value()?));
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.bump(),
+                Some(']') => {
+                    self.bump();
+                    return Ok(Value::Array(items));
+                }
+                Some(c) => return Err(self.error(JsonErrorKind::UnexpectedChar(c))),
+                None => return Err(self.error(JsonErrorKind::UnexpectedEnd)),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, JsonError> {
+        self.expect('"')?;
+        let mut s = String::new();
+        loop {
+            match self.peek() {
+                Some('"') => {
+                    self.bump();
+                    return Ok(s);
+                }
+                Some('\\') => {
+                    self.bump();
+                    let c = match self.peek() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('u') => {
+                            let hex = self.input.get(self.pos + 1..self.pos + 5);
+                            let code = hex.and_then(|hex| u32::from_str_radix(hex, 16).ok());
+                            match code.and_then(char::from_u32) {
+                                Some(c) => {
+                                    self.pos += 4;
+                                    c
+                                }
+                                None => return Err(self.error(JsonErrorKind::InvalidEscape)),
+                            }
+                        }
+                        Some(_) => return Err(self.error(JsonErrorKind::InvalidEscape)),
+                        None => return Err(self.error(JsonErrorKind::UnexpectedEnd)),
+                    };
+                    self.bump();
+                    s.push(c);
+                }
+                Some(c) => {
+                    self.bump();
+                    s.push(c);
+                }
+                None => return Err(self.error(JsonErrorKind::UnexpectedEnd)),
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Value, JsonError> {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if !(c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E')) {
+                break;
+            }
+            self.bump();
+        }
+        self.input[start..self.pos]
+            .parse::<f64>()
+            .map(Value::Number)
+            .map_err(|_| JsonError {
+                position: start,
+                kind: JsonErrorKind::InvalidNumber,
+            })
+    }
+
+    fn keyword(&mut self, keyword: &str, value: Value) -> Result<Value, JsonError> {
+        if self.input[self.pos..].starts_with(keyword) {
+            self.pos += keyword.len();
+            Ok(value)
+        } else {
+            Err(self.error(JsonErrorKind::UnexpectedChar('n')))
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), JsonError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.bump();
+                Ok(())
+            }
+            Some(c) => Err(self.error(JsonErrorKind::UnexpectedChar(c))),
+            None => Err(self.error(JsonErrorKind::UnexpectedEnd)),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) {
+        if let Some(c) = self.peek() {
+            self.pos += c.len_utf8();
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\n' | '\r' | '\t')) {
+            self.bump();
+        }
+    }
+
+    fn error(&self, kind: JsonErrorKind) -> JsonError {
+        JsonError {
+            position: self.pos,
+            kind,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unary(input: Unit, op: Op, label: &'static str) -> Unit {
+        let mut children = ArrayVec::new();
+        children.push(input);
+        Unit::with_child(0.5f64, children, op, label)
+    }
+
+    #[test]
+    fn test_round_trip_every_op() {
+        let a = Unit::new(0.50f64, "a");
+        let b = Unit::new(-0.75f64, "b");
+        let c = Unit::constant(0.1);
+        let mut root = unary(
+            unary((a * b + c).tanh(), Op::Sigmoid('s'), "sigmoid"),
+            Op::Relu('r'),
+            "relu",
+        );
+        root.label = "root";
+        root.grad = 1.0;
+        root.traverse_backward();
+
+        let json = root.to_json();
+        let loaded = Unit::from_json(&json).unwrap();
+        assert_eq!(loaded, root);
+        assert_eq!(loaded.to_json(), json);
+    }
+
+    #[test]
+    fn test_leaf_format() {
+        let mut a = Unit::new(2.0f64, "a \"quoted\"");
+        a.grad = -0.125;
+        assert_eq!(
+            a.to_json(),
+            "{\"label\":\"a \\\"quoted\\\"\",\"value\":2,\"grad\":-0.125,\"op\":null,\"prev\":[]}"
+        );
+    }
+
+    #[test]
+    fn test_non_finite_values() {
+        let mut a = Unit::new(f64::INFINITY, "a");
+        a.grad = f64::NAN;
+        let loaded = Unit::from_json(&a.to_json()).unwrap();
+        assert_eq!(loaded.value, f64::INFINITY);
+        assert!(loaded.grad.is_nan());
+    }
+
+    #[test]
+    fn test_accepts_whitespace_and_field_order() {
+        let json = r#"
+            { "prev": [], "op": null, "grad": 1e-3,
+              "value": -4.5, "label": "xA" }
+        "#;
+        let loaded = Unit::from_json(json).unwrap();
+        assert_eq!(loaded.label, "xA");
+        assert_eq!(loaded.value, -4.5);
+        assert_eq!(loaded.grad, 0.001);
+    }
+
+    #[test]
+    fn test_errors() {
+        let missing = r#"{"label":"a","value":1,"op":null,"prev":[]}"#;
+        assert_eq!(
+            Unit::from_json(missing).unwrap_err().kind,
+            JsonErrorKind::MissingField("grad")
+        );
+        let op = r#"{"label":"a","value":1,"grad":0,"op":{"kind":"pow","char":"^"},"prev":[]}"#;
+        assert_eq!(
+            Unit::from_json(op).unwrap_err().kind,
+            JsonErrorKind::UnknownOp("pow".to_string())
+        );
+        let leaf = r#"{"label":"a","value":1,"grad":0,"op":null,"prev":[]}"#;
+        let too_many = [
+            r#"{"label":"r","value":1,"grad":0,"op":{"kind":"add","char":"+"},"prev":["#,
+            leaf,
+            ",",
+            leaf,
+            ",",
+            leaf,
+            "]}",
+        ]
+        .concat();
+        assert_eq!(
+            Unit::from_json(&too_many).unwrap_err().kind,
+            JsonErrorKind::InvalidField("prev")
+        );
+        let childless =
+            r#"{"label":"r","value":1,"grad":0,"op":{"kind":"add","char":"+"},"prev":[]}"#;
+        let tanh_of_two = [
+            r#"{"label":"t","value":1,"grad":0,"op":{"kind":"tanh","char":"t"},"prev":["#,
+            leaf,
+            ",",
+            leaf,
+            "]}",
+        ]
+        .concat();
+        let leaf_with_child = [
+            r#"{"label":"a","value":1,"grad":0,"op":null,"prev":["#,
+            leaf,
+            "]}",
+        ]
+        .concat();
+        for json in [childless, &tanh_of_two, &leaf_with_child] {
+            assert_eq!(
+                Unit::from_json(json).unwrap_err().kind,
+                JsonErrorKind::InvalidField("prev")
+            );
+        }
+        let truncated = Unit::from_json(r#"{"label":"a""#).unwrap_err();
+        assert_eq!(truncated.kind, JsonErrorKind::UnexpectedEnd);
+        assert_eq!(
+            truncated.to_string(),
+            "unexpected end of input at position 12"
+        );
+        assert!(Unit::from_json("").is_err());
+        assert!(Unit::from_json(&[leaf, "x"].concat()).is_err());
+    }
+}
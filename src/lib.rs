@@ -1,4 +1,46 @@
 #![no_std]
+// Without `alloc` only the checkpoint reader and the random number generator
+// are left: `Unit` graphs box their children.
+pub mod checkpoint;
+#[cfg(feature = "alloc")]
+pub mod codegen;
+#[cfg(feature = "alloc")]
 pub mod core;
+#[cfg(feature = "std")]
+pub mod csv;
+#[cfg(feature = "alloc")]
+pub mod data;
+#[cfg(feature = "alloc")]
+pub mod dot;
+#[cfg(feature = "alloc")]
+pub mod generate;
+#[cfg(feature = "alloc")]
+pub mod idx;
+#[cfg(feature = "json")]
+pub mod json;
+#[cfg(feature = "alloc")]
+pub mod metrics;
+#[cfg(feature = "alloc")]
+pub mod nn;
+#[cfg(feature = "alloc")]
+pub mod optim;
+#[cfg(feature = "alloc")]
+pub mod parser;
+pub mod rand;
+#[cfg(feature = "alloc")]
+pub mod regularize;
+#[cfg(feature = "alloc")]
+pub mod schedule;
+#[cfg(feature = "alloc")]
+pub mod symbolic;
+#[cfg(feature = "alloc")]
+pub mod trace;
+#[cfg(feature = "alloc")]
+pub mod trainer;
+#[cfg(feature = "debug")]
+pub mod viz;
+#[cfg(feature = "alloc")]
 extern crate alloc;
 extern crate libm;
+#[cfg(feature = "std")]
+extern crate std;
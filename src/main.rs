@@ -1,345 +1,626 @@
-use majin::core::Op;
 use majin::core::Unit;
-use ratatui::{
-    backend::CrosstermBackend,
-    prelude::Rect,
-    widgets::{BorderType, Paragraph},
-    Terminal,
-};
-
-use crossterm::{
-    event::EnableMouseCapture, execute, terminal::enable_raw_mode, terminal::EnterAlternateScreen,
-};
-use std::collections::HashMap;
-use std::io;
-use tui_nodes::*;
-
-fn main() -> Result<(), io::Error> {
-    let root = create_unit_tree();
-
-    let (nodes, edges) = trace(&root);
+use majin::parser::parse;
+use std::collections::BTreeMap;
+use std::env;
+use std::process;
+
+#[cfg(feature = "debug")]
+mod dashboard;
+mod repl;
+mod train;
+
+const USAGE: &str = "\
+usage: majin <command> \"<expr>\" [--set a=2,b=3]
+       majin repl
+       majin train [--dashboard] [--epochs 300] [--lr 0.2] [--batch 32]
+                   [--clip 1.0] [--patience 20] [--cosine]
+                   [--csv data.csv [--label y] [--features a,b] [--one-hot]]
+                   [--dataset moons [--samples 100] [--noise 0.1]]
+
+commands:
+    eval      print the value of the expression
+    grad      print the gradient of every variable
+    show      open the expression graph in the terminal (needs the `debug` feature)
+    export    print the graph, with --format dot|json|text|ansi|svg
+              (text, ansi and svg draw it like `show` and need the `debug` feature)
+    repl      build and differentiate expressions interactively
+    train     fit a small MLP to XOR, with --dashboard to watch it live
+              (needs the `debug` feature); --csv fits a file instead, taking
+              the --label columns as targets (the last by default) and the
+              --features columns as inputs (all others by default), by name
+              or index; --one-hot encodes text labels. Features are
+              standardized. Reading CSV needs the `std` feature; --dataset
+              generates moons, circles, spirals, blobs, xor or linear samples.
+              Training is full-batch unless --batch is given; --clip caps the
+              grad norm, --patience stops once the loss stalls for that many
+              epochs and --cosine anneals the learning rate to 0";
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Eval,
+    Grad,
+    Show,
+    Export(Format),
+    Repl,
+    Train {
+        dashboard: bool,
+        settings: Settings,
+        source: Source,
+    },
+}
 
-    let node_metadata = generate_node_metadata(&nodes);
+// Training flags as given; unset ones keep the `train::Options` defaults.
+#[derive(Debug, PartialEq, Clone, Default)]
+struct Settings {
+    epochs: Option<usize>,
+    learning_rate: Option<f64>,
+    batch_size: Option<usize>,
+    clip: Option<f64>,
+    patience: Option<usize>,
+    cosine: bool,
+}
 
-    let node_layouts = create_node_layouts(&nodes, &node_metadata);
+// Where `train` gets its samples from.
+#[derive(Debug, PartialEq, Clone)]
+enum Source {
+    Xor,
+    Csv {
+        path: String,
+        labels: Option<String>,
+        features: Option<String>,
+        one_hot: bool,
+    },
+    Generated {
+        generator: Generator,
+        samples: usize,
+        noise: f64,
+    },
+}
 
-    let connections = create_connections(&nodes, &edges);
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Generator {
+    Moons,
+    Circles,
+    Spirals,
+    Blobs,
+    Xor,
+    Linear,
+}
 
-    print!("\x1b[2J\x1b[1;1H");
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Format {
+    Dot,
+    Json,
+    Text,
+    Ansi,
+    Svg,
+}
 
-    let mut terminal = setup_terminal()?;
+#[derive(Debug, PartialEq)]
+struct Args {
+    command: Command,
+    expr: String,
+    bindings: BTreeMap<&'static str, f64>,
+}
 
-    let space = Rect {
-        x: 0,
-        y: 0,
-        width: 100,
-        height: 100,
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args = match parse_args(&args) {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("error: {}\n\n{}", message, USAGE);
+            process::exit(2);
+        }
     };
-    let mut graph = NodeGraph::new(
-        node_layouts,
-        connections,
-        space.width as usize,
-        space.height as usize,
-    );
+    if let Err(message) = run(args) {
+        eprintln!("error: {}", message);
+        process::exit(1);
+    }
+}
 
-    terminal.draw(|f| {
-        graph.calculate();
+fn run(args: Args) -> Result<(), String> {
+    match args.command {
+        Command::Repl => return repl::run(),
+        Command::Train {
+            dashboard,
+            settings,
+            source,
+        } => return fit(dashboard, &settings, &source),
+        _ => {}
+    }
+    let mut root = parse(&args.expr, &args.bindings).map_err(|err| {
+        format!(
+            "{}\n    {}\n    {}^",
+            err,
+            args.expr,
+            " ".repeat(err.position)
+        )
+    })?;
+    match args.command {
+        Command::Eval => println!("{}", root.value),
+        Command::Grad => {
+            root.grad = 1.0;
+            root.traverse_backward();
+            for (label, grad) in root.leaf_grads() {
+                println!("{}: {}", label, grad);
+            }
+        }
+        Command::Show => show(&root)?,
+        Command::Export(format) => print!("{}", export(&root, format)?),
+        Command::Repl | Command::Train { .. } => unreachable!(),
+    }
+    Ok(())
+}
 
-        let zones = graph.split(space);
+#[cfg(feature = "debug")]
+fn show(root: &Unit) -> Result<(), String> {
+    majin::viz::show(root).map_err(|err| err.to_string())
+}
 
-        for (idx, ea_zone) in zones.into_iter().enumerate() {
-            let label = &node_metadata[idx].0;
-            f.render_widget(Paragraph::new(label.clone()), ea_zone);
-        }
-        f.render_stateful_widget(graph, space, &mut ());
-    })?;
+#[cfg(not(feature = "debug"))]
+fn show(_root: &Unit) -> Result<(), String> {
+    Err("`show` needs majin to be built with the `debug` feature".to_owned())
+}
 
+fn fit(dashboard: bool, settings: &Settings, source: &Source) -> Result<(), String> {
+    let defaults = train::Options::default();
+    let options = &train::Options {
+        epochs: settings.epochs.unwrap_or(defaults.epochs),
+        learning_rate: settings.learning_rate.unwrap_or(defaults.learning_rate),
+        batch_size: settings.batch_size,
+        clip: settings.clip,
+        patience: settings.patience,
+        cosine: settings.cosine,
+        ..defaults
+    };
+    let samples = load(source, options.seed)?;
+    let mut mlp = train::model(&samples, options.seed);
+    if dashboard {
+        return watch(&mut mlp, &samples, options);
+    }
+    let every = (options.epochs / 10).max(1);
+    train::train(&mut mlp, &samples, options, &mut |_, stats| {
+        if stats.epoch % every == 0 || stats.epoch == options.epochs {
+            println!(
+                "epoch {}: loss {:.4}, accuracy {:.1}%",
+                stats.epoch,
+                stats.loss,
+                stats.accuracy * 100.0
+            );
+        }
+        true
+    });
     Ok(())
 }
 
-fn create_unit_tree() -> Unit {
-    let a = Unit::new(2.0f64, "a");
-    let b = Unit::new(3.0f64, "b");
-    let c = Unit::new(4.0f64, "c");
-    let d = Unit::new(5.0f64, "d");
-    let e = Unit::new(6.0f64, "e");
-    // 25 = (2 + 3) * 4 + 5 + 6
-    let mut ab = a + b;
-    ab.label = "ab";
-    let mut abc = ab * c;
-    abc.label = "abc";
-    let mut abcd = abc + d;
-    abcd.label = "abcd";
-    let mut root = abcd + e;
-    root.label = "root";
-
-    root
+fn load(source: &Source, seed: u64) -> Result<Vec<majin::data::Sample>, String> {
+    match source {
+        Source::Xor => Ok(majin::generate::xor_corners().into_samples()),
+        Source::Generated {
+            generator,
+            samples,
+            noise,
+        } => Ok(generate(*generator, *samples, *noise, seed).into_samples()),
+        Source::Csv {
+            path,
+            labels,
+            features,
+            one_hot,
+        } => load_csv(path, labels.as_deref(), features.as_deref(), *one_hot),
+    }
 }
 
-fn generate_node_metadata(
-    nodes: &Vec<(&Unit, Option<&Unit>, usize)>,
-) -> Vec<(String, String, String)> {
-    nodes
-        .iter()
-        .map(|(node, _, _)| {
-            let inner_label = match node.op {
-                Some(Op::Add(_)) => "+",
-                Some(Op::Mul(_)) => "*",
-                _ => "?",
-            };
-            (
-                format!("{}:{}", inner_label, node.value),
-                inner_label.to_owned(),
-                node.label.to_owned(),
-            )
-        })
-        .collect()
+// Multi-class sets get three classes, and the linear targets stay inside the
+// (-1, 1) range of the tanh output.
+fn generate(generator: Generator, samples: usize, noise: f64, seed: u64) -> majin::data::InMemory {
+    use majin::generate;
+    match generator {
+        Generator::Moons => generate::moons(samples, noise, seed),
+        Generator::Circles => generate::circles(samples, noise, 0.5, seed),
+        Generator::Spirals => generate::spirals(samples, 3, noise, seed),
+        Generator::Blobs => generate::blobs(samples, 3, noise, seed),
+        Generator::Xor => generate::xor(samples, noise, seed),
+        Generator::Linear => generate::linear(samples, &[0.5, -0.3], 0.1, noise, seed),
+    }
 }
 
-fn create_node_layouts<'a>(
-    nodes: &'a Vec<(&'a Unit, Option<&'a Unit>, usize)>,
-    node_metadata: &'a [(String, String, String)],
-) -> Vec<NodeLayout<'a>> {
-    nodes
-        .iter()
-        .enumerate()
-        .map(|(index, (node, _, _))| {
-            let title = &node_metadata[index].2;
-            let mut layout = NodeLayout::new((12, 5))
-                .with_title(title)
-                .with_border_type(BorderType::Rounded);
-
-            match node.op {
-                Some(Op::Add(_)) => {
-                    layout = layout.with_border_type(BorderType::Thick);
-                }
-                Some(Op::Mul(_)) => {
-                    layout = layout.with_border_type(BorderType::Double);
-                }
-                _ => {}
-            }
+// Targets are one-hot encoded as ±1 to match the tanh output layer.
+#[cfg(feature = "std")]
+fn load_csv(
+    path: &str,
+    labels: Option<&str>,
+    features: Option<&str>,
+    one_hot: bool,
+) -> Result<Vec<majin::data::Sample>, String> {
+    use majin::csv::{self, Column, CsvOptions};
+    let columns = |list: &str| list.split(',').map(Column::parse).collect::<Vec<_>>();
+    let options = CsvOptions {
+        labels: labels.map(columns).unwrap_or_default(),
+        features: features.map(columns),
+        one_hot,
+        cold: -1.0,
+        standardize: true,
+        ..CsvOptions::default()
+    };
+    let data = csv::load(path, &options).map_err(|err| format!("{}: {}", path, err))?;
+    Ok(data.dataset.into_samples())
+}
 
-            layout
-        })
-        .collect()
+#[cfg(not(feature = "std"))]
+fn load_csv(
+    _path: &str,
+    _labels: Option<&str>,
+    _features: Option<&str>,
+    _one_hot: bool,
+) -> Result<Vec<majin::data::Sample>, String> {
+    Err("--csv needs majin to be built with the `std` feature".to_owned())
 }
 
-fn create_connections(
-    nodes: &Vec<(&Unit, Option<&Unit>, usize)>,
-    edges: &Vec<(&Unit, &Unit)>,
-) -> Vec<Connection> {
-    let mut port_usage: HashMap<usize, usize> = HashMap::new();
-
-    edges
-        .iter()
-        .map(|(from, to)| {
-            let from_index = nodes
-                .iter()
-                .position(|(node, _, _)| *node == *from)
-                .unwrap();
-            let to_index = nodes.iter().position(|(node, _, _)| *node == *to).unwrap();
-
-            let from_port = *port_usage.entry(from_index).or_insert(0);
-            let to_port = *port_usage.entry(to_index).or_insert(0);
-
-            *port_usage.get_mut(&from_index).unwrap() += 1;
-            *port_usage.get_mut(&to_index).unwrap() += 1;
-
-            match nodes[to_index].0.op {
-                Some(Op::Add(_)) => Connection::new(from_index, from_port, to_index, to_port)
-                    .with_line_type(LineType::Thick),
-                Some(Op::Mul(_)) => Connection::new(from_index, from_port, to_index, to_port)
-                    .with_line_type(LineType::Double),
-                _ => Connection::new(from_index, from_port, to_index, to_port)
-                    .with_line_type(LineType::Plain),
-            }
-        })
-        .collect()
+#[cfg(feature = "debug")]
+fn watch(
+    mlp: &mut majin::nn::Mlp,
+    samples: &[(Vec<f64>, Vec<f64>)],
+    options: &train::Options,
+) -> Result<(), String> {
+    dashboard::run(mlp, samples, options).map_err(|err| err.to_string())
 }
 
-fn setup_terminal() -> Result<Terminal<CrosstermBackend<std::io::Stdout>>, io::Error> {
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
-    let terminal = Terminal::new(backend)?;
-    Ok(terminal)
+#[cfg(not(feature = "debug"))]
+fn watch(
+    _mlp: &mut majin::nn::Mlp,
+    _samples: &[(Vec<f64>, Vec<f64>)],
+    _options: &train::Options,
+) -> Result<(), String> {
+    Err("--dashboard needs majin to be built with the `debug` feature".to_owned())
 }
 
-fn trace(root: &Unit) -> (Vec<(&Unit, Option<&Unit>, usize)>, Vec<(&Unit, &Unit)>) {
-    let mut nodes = Vec::new();
-    let mut edges = Vec::new();
-
-    fn build<'a>(
-        v: &'a Unit,
-        parent: Option<&'a Unit>,
-        nodes: &mut Vec<(&'a Unit, Option<&'a Unit>, usize)>,
-        edges: &mut Vec<(&'a Unit, &'a Unit)>,
-        level: usize,
-    ) {
-        // Check if the current node `v` is already in `nodes_with_levels`:
-        // - `nodes_with_levels.iter()`: Creates an iterator over the `nodes_with_levels` vector.
-        //   Each item in the iterator is a reference to a tuple `(&Unit, usize)`.
-        // - `.any(|(node, _)| *node == v)`: The `.any()` method checks if any item in the iterator
-        //   satisfies the provided condition. The closure `|(node, _)| *node == v` is the condition.
-        //   This closure takes each tuple `(node, level)` (where `level` is ignored with `_`) and checks
-        //   if `node` (which is a reference to a `Unit`) matches the node `v`.
-        // - `*node`: Dereferences the `&Unit` reference, so you can directly compare it to `v`.
-        // - `!`: Negates the result. If `.any()` returns `true` (meaning the node is already in the vector),
-        //   the `!` turns it into `false`, indicating that the node should not be added again.
-        let node_exists = nodes.iter().any(|(node, _, _)| *node == v);
-        if !node_exists {
-            nodes.push((v, parent, level));
-            for prev in &v.prev {
-                edges.push((prev.as_ref(), v));
-                build(prev.as_ref(), Some(v), nodes, edges, level + 1);
+fn export(root: &Unit, format: Format) -> Result<String, String> {
+    match format {
+        #[cfg(feature = "debug")]
+        Format::Text => Ok(majin::viz::snapshot::to_text(&majin::viz::render_graph(
+            root,
+        ))),
+        #[cfg(feature = "debug")]
+        Format::Ansi => Ok(majin::viz::snapshot::to_ansi(&majin::viz::render_graph(
+            root,
+        ))),
+        #[cfg(feature = "debug")]
+        Format::Svg => Ok(majin::viz::snapshot::to_svg(&majin::viz::render_graph(
+            root,
+        ))),
+        #[cfg(not(feature = "debug"))]
+        Format::Text | Format::Ansi | Format::Svg => {
+            Err("text, ansi and svg export need the `debug` feature".to_owned())
+        }
+        Format::Dot => Ok(root.to_dot()),
+        #[cfg(feature = "json")]
+        Format::Json => Ok(root.to_json() + "\n"),
+        #[cfg(not(feature = "json"))]
+        Format::Json => Err("json export needs the `json` feature".to_owned()),
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<Args, String> {
+    let mut args = args.iter();
+    let command = args.next().ok_or("missing command")?;
+    let mut expr = None;
+    let mut format = None;
+    let mut bindings = BTreeMap::new();
+    let mut dashboard = false;
+    let mut settings = Settings::default();
+    let mut csv = None;
+    let mut labels = None;
+    let mut features = None;
+    let mut one_hot = false;
+    let mut generator = None;
+    let mut samples = None;
+    let mut noise = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--set" => {
+                let assignments = args.next().ok_or("--set needs a value")?;
+                parse_assignments(assignments, &mut bindings)?;
+            }
+            "--format" => {
+                format = match args.next().map(String::as_str) {
+                    Some("dot") => Some(Format::Dot),
+                    Some("json") => Some(Format::Json),
+                    Some("text") => Some(Format::Text),
+                    Some("ansi") => Some(Format::Ansi),
+                    Some("svg") => Some(Format::Svg),
+                    Some(other) => return Err(format!("unknown format `{}`", other)),
+                    None => return Err("--format needs a value".to_owned()),
+                };
+            }
+            "--dashboard" => dashboard = true,
+            "--epochs" => settings.epochs = Some(flag_value(args.next(), "--epochs")?),
+            "--lr" => settings.learning_rate = Some(flag_value(args.next(), "--lr")?),
+            "--batch" => settings.batch_size = Some(flag_value(args.next(), "--batch")?),
+            "--clip" => settings.clip = Some(flag_value(args.next(), "--clip")?),
+            "--patience" => settings.patience = Some(flag_value(args.next(), "--patience")?),
+            "--cosine" => settings.cosine = true,
+            "--csv" => csv = Some(flag_value(args.next(), "--csv")?),
+            "--label" => labels = Some(flag_value(args.next(), "--label")?),
+            "--features" => features = Some(flag_value(args.next(), "--features")?),
+            "--one-hot" => one_hot = true,
+            "--dataset" => {
+                generator = match args.next().map(String::as_str) {
+                    Some("moons") => Some(Generator::Moons),
+                    Some("circles") => Some(Generator::Circles),
+                    Some("spirals") => Some(Generator::Spirals),
+                    Some("blobs") => Some(Generator::Blobs),
+                    Some("xor") => Some(Generator::Xor),
+                    Some("linear") => Some(Generator::Linear),
+                    Some(other) => return Err(format!("unknown dataset `{}`", other)),
+                    None => return Err("--dataset needs a value".to_owned()),
+                };
             }
+            "--samples" => samples = Some(flag_value(args.next(), "--samples")?),
+            "--noise" => noise = Some(flag_value(args.next(), "--noise")?),
+            flag if flag.starts_with("--") => return Err(format!("unknown flag `{}`", flag)),
+            _ if expr.is_none() => expr = Some(arg.clone()),
+            _ => return Err(format!("unexpected argument `{}`", arg)),
         }
     }
+    let command = match command.as_str() {
+        "eval" => Command::Eval,
+        "grad" => Command::Grad,
+        "show" => Command::Show,
+        "export" => Command::Export(format.ok_or("export needs --format dot|json|text|ansi|svg")?),
+        "repl" => Command::Repl,
+        "train" => Command::Train {
+            dashboard,
+            settings,
+            source: match (csv, generator) {
+                (Some(_), Some(_)) => {
+                    return Err("--csv and --dataset can't be combined".to_owned())
+                }
+                (Some(path), None) => Source::Csv {
+                    path,
+                    labels,
+                    features,
+                    one_hot,
+                },
+                (None, _) if labels.is_some() || features.is_some() || one_hot => {
+                    return Err("--label, --features and --one-hot need --csv".to_owned())
+                }
+                (None, Some(generator)) => Source::Generated {
+                    generator,
+                    samples: samples.unwrap_or(100),
+                    noise: noise.unwrap_or(0.1),
+                },
+                (None, None) if samples.is_some() || noise.is_some() => {
+                    return Err("--samples and --noise need --dataset".to_owned())
+                }
+                (None, None) => Source::Xor,
+            },
+        },
+        other => return Err(format!("unknown command `{}`", other)),
+    };
+    let expr = match command {
+        Command::Repl => expr.unwrap_or_default(),
+        Command::Train { .. } if expr.is_some() => {
+            return Err("train takes no expression".to_owned())
+        }
+        Command::Train { .. } => String::new(),
+        _ => expr.ok_or("missing expression")?,
+    };
+    Ok(Args {
+        command,
+        expr,
+        bindings,
+    })
+}
+
+fn flag_value<T: std::str::FromStr>(value: Option<&String>, flag: &str) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{} needs a value", flag))?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid value for {}: `{}`", flag, value))
+}
 
-    build(root, None, &mut nodes, &mut edges, 0);
-    (nodes, edges)
+// Parses `a=2,b=3` into `bindings`. Variable names become `Unit` labels, which
+// are `&'static str`, so they are leaked; the CLI only creates a handful per run.
+fn parse_assignments(
+    assignments: &str,
+    bindings: &mut BTreeMap<&'static str, f64>,
+) -> Result<(), String> {
+    for assignment in assignments.split(',').filter(|a| !a.trim().is_empty()) {
+        let (name, value) = assignment
+            .split_once('=')
+            .ok_or_else(|| format!("expected name=value, got `{}`", assignment))?;
+        let value: f64 = value
+            .trim()
+            .parse()
+            .map_err(|_| format!("invalid value for `{}`: `{}`", name.trim(), value.trim()))?;
+        bindings.insert(Box::leak(name.trim().to_owned().into_boxed_str()), value);
+    }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
     #[test]
-    fn test_trace_single_node() {
-        let root = Unit::new(0.0f64, "root");
-        let (nodes, edges) = trace(&root);
+    fn test_parse_eval_with_bindings() {
+        let parsed = parse_args(&args(&["eval", "a * b", "--set", "a=2, b=3"])).unwrap();
+        assert_eq!(parsed.command, Command::Eval);
+        assert_eq!(parsed.expr, "a * b");
+        assert_eq!(parsed.bindings.len(), 2);
+        assert_eq!(parsed.bindings["a"], 2.0);
+        assert_eq!(parsed.bindings["b"], 3.0);
+    }
 
-        assert_eq!(nodes.len(), 1);
-        assert_eq!(nodes[0].0, &root);
-        assert!(edges.is_empty());
+    #[test]
+    fn test_parse_export_needs_format() {
+        let parsed = parse_args(&args(&["export", "--format", "dot", "a"])).unwrap();
+        assert_eq!(parsed.command, Command::Export(Format::Dot));
+        let parsed = parse_args(&args(&["export", "a", "--format", "svg"])).unwrap();
+        assert_eq!(parsed.command, Command::Export(Format::Svg));
+        assert!(parse_args(&args(&["export", "a"])).is_err());
+        assert!(parse_args(&args(&["export", "a", "--format", "png"])).is_err());
     }
 
     #[test]
-    fn test_trace_single_node_f32() {
-        let root = Unit::new(0.0f64, "root");
-        let (nodes, edges) = trace(&root);
-
-        assert_eq!(nodes.len(), 1);
-        // (&root, parent, level)
-        // first element in the vector is the root node
-        // the parent is None because it is the root node
-        // the level is 0 because it is the root node
-        // first element in the tuple is a reference to the root node
-        assert_eq!(nodes[0].0, &root);
-        assert!(edges.is_empty());
+    fn test_parse_errors() {
+        assert!(parse_args(&args(&[])).is_err());
+        assert!(parse_args(&args(&["eval"])).is_err());
+        assert!(parse_args(&args(&["frobnicate", "a"])).is_err());
+        assert!(parse_args(&args(&["eval", "a", "b"])).is_err());
+        assert!(parse_args(&args(&["eval", "a", "--set", "a"])).is_err());
+        assert!(parse_args(&args(&["eval", "a", "--set", "a=x"])).is_err());
     }
 
     #[test]
-    fn test_trace_multiple_nodes_f32() {
-        let leaf1 = Unit::new(2.0f64, "leaf1");
-        let leaf2 = Unit::new(3.0f64, "leaf2");
-        let root = leaf1.clone() + leaf2.clone();
-
-        let (nodes, edges) = trace(&root);
-
-        assert_eq!(nodes.len(), 3);
-        // search for the reference to the root node
-        assert!(nodes.iter().any(|(node, _, _)| *node == &root));
-        // search for the reference to the leaf1 node
-        assert!(nodes.iter().any(|(node, _, _)| *node == &leaf1));
-        // search for the reference to the leaf2 node
-        assert!(nodes.iter().any(|(node, _, _)| *node == &leaf2));
-
-        assert_eq!(edges.len(), 2);
-        assert!(edges.iter().any(|(n1, n2)| *n1 == &leaf1 && *n2 == &root));
-        assert!(edges.iter().any(|(n1, n2)| *n1 == &leaf2 && *n2 == &root));
+    fn test_parse_repl_needs_no_expression() {
+        let parsed = parse_args(&args(&["repl"])).unwrap();
+        assert_eq!(parsed.command, Command::Repl);
     }
 
     #[test]
-    fn test_trace_multiple_nodes() {
-        let leaf1 = Unit::new(2.0f64, "leaf1");
-        let leaf2 = Unit::new(3.0f64, "leaf2");
-        let root = leaf1.clone() + leaf2.clone();
+    fn test_parse_train() {
+        let parsed = parse_args(&args(&["train", "--dashboard", "--epochs", "50"])).unwrap();
+        assert_eq!(
+            parsed.command,
+            Command::Train {
+                dashboard: true,
+                settings: Settings {
+                    epochs: Some(50),
+                    ..Settings::default()
+                },
+                source: Source::Xor,
+            }
+        );
+        let parsed = parse_args(&args(&["train", "--lr", "0.05"])).unwrap();
+        assert_eq!(
+            parsed.command,
+            Command::Train {
+                dashboard: false,
+                settings: Settings {
+                    learning_rate: Some(0.05),
+                    ..Settings::default()
+                },
+                source: Source::Xor,
+            }
+        );
+        let parsed = parse_args(&args(&[
+            "train",
+            "--batch",
+            "2",
+            "--clip",
+            "1.5",
+            "--patience",
+            "10",
+            "--cosine",
+        ]))
+        .unwrap();
+        assert_eq!(
+            parsed.command,
+            Command::Train {
+                dashboard: false,
+                settings: Settings {
+                    batch_size: Some(2),
+                    clip: Some(1.5),
+                    patience: Some(10),
+                    cosine: true,
+                    ..Settings::default()
+                },
+                source: Source::Xor,
+            }
+        );
+        assert!(parse_args(&args(&["train", "--batch", "half"])).is_err());
+        assert!(parse_args(&args(&["train", "--epochs", "many"])).is_err());
+        assert!(parse_args(&args(&["train", "a * b"])).is_err());
+    }
 
-        let (nodes, edges) = trace(&root);
+    #[test]
+    fn test_parse_train_csv() {
+        let parsed = parse_args(&args(&[
+            "train",
+            "--csv",
+            "iris.csv",
+            "--label",
+            "species",
+            "--one-hot",
+        ]))
+        .unwrap();
+        assert_eq!(
+            parsed.command,
+            Command::Train {
+                dashboard: false,
+                settings: Settings::default(),
+                source: Source::Csv {
+                    path: "iris.csv".to_owned(),
+                    labels: Some("species".to_owned()),
+                    features: None,
+                    one_hot: true,
+                },
+            }
+        );
+        assert!(parse_args(&args(&["train", "--label", "y"])).is_err());
+        assert!(parse_args(&args(&["train", "--csv"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_train_dataset() {
+        let parsed = parse_args(&args(&["train", "--dataset", "moons", "--noise", "0.2"])).unwrap();
+        assert_eq!(
+            parsed.command,
+            Command::Train {
+                dashboard: false,
+                settings: Settings::default(),
+                source: Source::Generated {
+                    generator: Generator::Moons,
+                    samples: 100,
+                    noise: 0.2,
+                },
+            }
+        );
+        assert!(parse_args(&args(&["train", "--dataset", "swirls"])).is_err());
+        assert!(parse_args(&args(&["train", "--samples", "10"])).is_err());
+        assert!(parse_args(&args(&["train", "--dataset", "xor", "--csv", "a.csv"])).is_err());
+    }
 
-        assert_eq!(nodes.len(), 3);
-        assert!(nodes.iter().any(|(node, _, _)| *node == &root));
-        assert!(nodes.iter().any(|(node, _, _)| *node == &leaf1));
-        assert!(nodes.iter().any(|(node, _, _)| *node == &leaf2));
+    #[test]
+    fn test_load_generated() {
+        let samples = load(
+            &Source::Generated {
+                generator: Generator::Spirals,
+                samples: 30,
+                noise: 0.0,
+            },
+            1,
+        )
+        .unwrap();
+        assert_eq!(samples.len(), 30);
+        assert_eq!(samples[0].1.len(), 3);
+    }
 
-        assert_eq!(edges.len(), 2);
-        assert!(edges.iter().any(|(n1, n2)| *n1 == &leaf1 && *n2 == &root));
-        assert!(edges.iter().any(|(n1, n2)| *n1 == &leaf2 && *n2 == &root));
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_load_csv() {
+        let path = std::env::temp_dir().join(format!("majin-cli-{}.csv", process::id()));
+        std::fs::write(&path, "a,b,class\n0,0,no\n1,1,yes\n").unwrap();
+        let path = path.to_str().unwrap().to_owned();
+        let samples = load_csv(&path, Some("class"), Some("b"), true);
+        let missing = load_csv(&path, Some("colour"), None, false);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            samples.unwrap(),
+            vec![(vec![-1.0], vec![1.0, -1.0]), (vec![1.0], vec![-1.0, 1.0])]
+        );
+        assert!(missing.unwrap_err().ends_with("unknown column `colour`"));
     }
 
     #[test]
-    fn test_trace_deep_tree() {
-        let leaf1 = Unit::new(2.0f64, "leaf1");
-        let leaf2 = Unit::new(3.0f64, "leaf2");
-        let leaf3 = Unit::new(4.0f64, "leaf3");
-        let leaf4 = Unit::new(5.0f64, "leaf4");
-        // 25 = (2 + 3) * 4 + 5
-        let root = (leaf1.clone() + leaf2.clone()) * leaf3.clone() + leaf4.clone();
-
-        let (nodes, edges) = trace(&root);
-
-        assert_eq!(nodes.len(), 7);
-
-        // Validate the root node and its connections
-        assert!(nodes.iter().any(|(node, _, _)| *node == &root)); // root 25
-        assert_eq!(root.value, 25.0);
-
-        // Validate connections and operations
-        assert!(nodes.iter().any(|(node, _, _)| **node == *root.prev[0])); // 20 (result of 5 * 4)
-        assert_eq!(root.prev[0].value, 20.0);
-
-        assert!(nodes.iter().any(|(node, _, _)| **node == *root.prev[1])); // 5
-        assert_eq!(root.prev[1].value, 5.0);
-
-        assert!(nodes
-            .iter()
-            .any(|(node, _, _)| **node == *root.prev[0].prev[0])); // 5 (result of 2 + 3)
-        assert_eq!(root.prev[0].prev[0].value, 5.0);
-
-        assert!(nodes
-            .iter()
-            .any(|(node, _, _)| **node == *root.prev[0].prev[1])); // 4
-        assert_eq!(root.prev[0].prev[1].value, 4.0);
-
-        assert!(nodes
-            .iter()
-            .any(|(node, _, _)| **node == *root.prev[0].prev[0].prev[0])); // 2
-        assert_eq!(root.prev[0].prev[0].prev[0].value, 2.0);
-
-        assert!(nodes
-            .iter()
-            .any(|(node, _, _)| **node == *root.prev[0].prev[0].prev[1])); // 3
-        assert_eq!(root.prev[0].prev[0].prev[1].value, 3.0);
-
-        // Validate the edges
-        assert_eq!(edges.len(), 6);
-        assert!(edges
-            .iter()
-            .any(|(n1, n2)| **n1 == *root.prev[0] && **n2 == root)); // 20 -> root
-        assert!(edges
-            .iter()
-            .any(|(n1, n2)| **n1 == *root.prev[1] && **n2 == root)); // 5 -> root
-
-        assert!(edges
-            .iter()
-            .any(|(n1, n2)| **n1 == *root.prev[0].prev[0] && **n2 == *root.prev[0])); // 5 -> 20
-        assert!(edges
-            .iter()
-            .any(|(n1, n2)| **n1 == *root.prev[0].prev[1] && **n2 == *root.prev[0])); // 4 -> 20
-
-        assert!(
-            edges
-                .iter()
-                .any(|(n1, n2)| **n1 == *root.prev[0].prev[0].prev[0]
-                    && **n2 == *root.prev[0].prev[0])
-        ); // 2 -> 5
-        assert!(
-            edges
-                .iter()
-                .any(|(n1, n2)| **n1 == *root.prev[0].prev[0].prev[1]
-                    && **n2 == *root.prev[0].prev[0])
-        ); // 3 -> 5
+    fn test_run_reports_parse_errors() {
+        let parsed = parse_args(&args(&["eval", "a + x", "--set", "a=1"])).unwrap();
+        let err = run(parsed).unwrap_err();
+        assert!(err.starts_with("unknown variable `x` at position 4"));
     }
 }
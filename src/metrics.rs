@@ -0,0 +1,276 @@
+// Evaluation metrics over plain numbers, read out of `Unit` values with
+// `values` or `Mlp::predict`.
+//
+// Classifiers are scored on class indices. `class_of` turns a model's outputs
+// into one the way training judges them: the sign of a single output (class 1
+// when positive), or the largest of several, which fits ±1 one-hot targets
+// too. Ratios with nothing to count, such as the precision of a class never
+// predicted, are 0.
+use crate::core::Unit;
+use alloc::vec;
+use alloc::vec::Vec;
+
+pub fn values(units: &[Unit]) -> Vec<f64> {
+    units.iter().map(|unit| unit.value).collect()
+}
+
+// The first of the largest values; `None` when empty.
+pub fn argmax(values: &[f64]) -> Option<usize> {
+    let mut best: Option<usize> = None;
+    for (index, value) in values.iter().enumerate() {
+        if best.is_none_or(|best| *value > values[best]) {
+            best = Some(index);
+        }
+    }
+    best
+}
+
+pub fn class_of(outputs: &[f64]) -> usize {
+    match outputs {
+        [output] => usize::from(*output > 0.0),
+        _ => argmax(outputs).unwrap_or(0),
+    }
+}
+
+// Share of `predicted` classes equal to the `actual` ones, pairwise.
+pub fn accuracy(predicted: &[usize], actual: &[usize]) -> f64 {
+    let correct = predicted.iter().zip(actual).filter(|(p, a)| p == a).count();
+    ratio(correct, predicted.len().min(actual.len()))
+}
+
+// Counts of every (actual, predicted) pair of classes, in a row per actual
+// class. Classes at or above `classes` are left out, and count 0 when asked
+// for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfusionMatrix {
+    classes: usize,
+    counts: Vec<usize>,
+}
+
+impl ConfusionMatrix {
+    pub fn new(predicted: &[usize], actual: &[usize], classes: usize) -> Self {
+        let mut counts = vec![0; classes * classes];
+        for (predicted, actual) in predicted.iter().zip(actual) {
+            if *predicted < classes && *actual < classes {
+                counts[actual * classes + predicted] += 1;
+            }
+        }
+        ConfusionMatrix { classes, counts }
+    }
+
+    pub fn classes(&self) -> usize {
+        self.classes
+    }
+
+    pub fn get(&self, actual: usize, predicted: usize) -> usize {
+        match actual < self.classes && predicted < self.classes {
+            true => self.counts[actual * self.classes + predicted],
+            false => 0,
+        }
+    }
+
+    // Empty for a class out of range.
+    pub fn row(&self, actual: usize) -> &[usize] {
+        match actual < self.classes {
+            true => &self.counts[actual * self.classes..(actual + 1) * self.classes],
+            false => &[],
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.counts.iter().sum()
+    }
+
+    pub fn accuracy(&self) -> f64 {
+        let correct = (0..self.classes).map(|class| self.get(class, class)).sum();
+        ratio(correct, self.total())
+    }
+
+    // Share of the predictions of `class` that are right.
+    pub fn precision(&self, class: usize) -> f64 {
+        let predicted = (0..self.classes)
+            .map(|actual| self.get(actual, class))
+            .sum();
+        ratio(self.get(class, class), predicted)
+    }
+
+    // Share of the samples of `class` that are found.
+    pub fn recall(&self, class: usize) -> f64 {
+        ratio(self.get(class, class), self.row(class).iter().sum())
+    }
+
+    pub fn f1(&self, class: usize) -> f64 {
+        let (precision, recall) = (self.precision(class), self.recall(class));
+        match precision + recall {
+            sum if sum > 0.0 => 2.0 * precision * recall / sum,
+            _ => 0.0,
+        }
+    }
+
+    // F1 averaged over classes, each counting the same.
+    pub fn macro_f1(&self) -> f64 {
+        let sum: f64 = (0..self.classes).map(|class| self.f1(class)).sum();
+        sum / self.classes.max(1) as f64
+    }
+}
+
+// Area under the ROC curve: the chance that a random positive scores above a
+// random negative, ties counting half. `None` without both kinds of samples,
+// or with a score that isn't finite, as from a model that diverged.
+pub fn roc_auc(scores: &[f64], positives: &[bool]) -> Option<f64> {
+    if scores.iter().any(|score| !score.is_finite()) {
+        return None;
+    }
+    let mut pairs: Vec<(f64, bool)> = scores
+        .iter()
+        .copied()
+        .zip(positives.iter().copied())
+        .collect();
+    pairs.sort_by(|a, b| a.0.total_cmp(&b.0));
+    // sum of the (1-based, tie-averaged) ranks of the positives
+    let mut rank_sum = 0.0;
+    let mut start = 0;
+    while start < pairs.len() {
+        let end = start
+            + pairs[start..]
+                .iter()
+                .take_while(|p| p.0 == pairs[start].0)
+                .count();
+        let rank = (start + 1 + end) as f64 / 2.0;
+        rank_sum += rank * pairs[start..end].iter().filter(|p| p.1).count() as f64;
+        start = end;
+    }
+    let positive = pairs.iter().filter(|p| p.1).count();
+    let negative = pairs.len() - positive;
+    if positive == 0 || negative == 0 {
+        return None;
+    }
+    let positive_pairs = rank_sum - (positive * (positive + 1)) as f64 / 2.0;
+    Some(positive_pairs / (positive * negative) as f64)
+}
+
+pub fn mean_absolute_error(predicted: &[f64], actual: &[f64]) -> f64 {
+    let sum: f64 = predicted
+        .iter()
+        .zip(actual)
+        .map(|(p, a)| (p - a).abs())
+        .sum();
+    sum / predicted.len().min(actual.len()).max(1) as f64
+}
+
+// Coefficient of determination, 1 - residual / total sum of squares. With
+// constant `actual` values it is 1 for a perfect fit and 0 otherwise.
+pub fn r_squared(predicted: &[f64], actual: &[f64]) -> f64 {
+    let count = predicted.len().min(actual.len());
+    let mean = actual[..count].iter().sum::<f64>() / count.max(1) as f64;
+    let residual: f64 = predicted
+        .iter()
+        .zip(actual)
+        .map(|(p, a)| (a - p) * (a - p))
+        .sum();
+    let total: f64 = actual[..count]
+        .iter()
+        .map(|a| (a - mean) * (a - mean))
+        .sum();
+    match (total > 0.0, residual > 0.0) {
+        (true, _) => 1.0 - residual / total,
+        (false, false) => 1.0,
+        (false, true) => 0.0,
+    }
+}
+
+fn ratio(count: usize, total: usize) -> f64 {
+    match total {
+        0 => 0.0,
+        _ => count as f64 / total as f64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_class_of() {
+        assert_eq!(
+            values(&[Unit::constant(0.5), Unit::new(-1.0, "x")]),
+            [0.5, -1.0]
+        );
+        assert_eq!(class_of(&[0.3]), 1);
+        assert_eq!(class_of(&[-0.3]), 0);
+        assert_eq!(class_of(&[-1.0, 0.2, 0.2]), 1);
+        assert_eq!(argmax(&[]), None);
+        assert_eq!(accuracy(&[0, 1, 2, 1], &[0, 1, 1, 1]), 0.75);
+        assert_eq!(accuracy(&[], &[]), 0.0);
+    }
+
+    #[test]
+    fn test_confusion_matrix() {
+        let actual = [0, 0, 0, 1, 1, 2, 2, 2, 2];
+        let predicted = [0, 0, 1, 1, 2, 2, 2, 2, 0];
+        let matrix = ConfusionMatrix::new(&predicted, &actual, 3);
+        assert_eq!(matrix.row(0), [2, 1, 0]);
+        assert_eq!(matrix.row(1), [0, 1, 1]);
+        assert_eq!(matrix.row(2), [1, 0, 3]);
+        assert_eq!(matrix.total(), 9);
+        assert_eq!(matrix.accuracy(), accuracy(&predicted, &actual));
+        assert_eq!(matrix.accuracy(), 6.0 / 9.0);
+        assert_eq!(matrix.precision(2), 0.75);
+        assert_eq!(matrix.recall(2), 0.75);
+        assert_eq!(matrix.f1(2), 0.75);
+        assert_eq!(matrix.precision(1), 0.5);
+        assert_eq!(matrix.recall(1), 0.5);
+        // precision 2/3, recall 2/3
+        assert!((matrix.f1(0) - 2.0 / 3.0).abs() < 1e-15);
+        assert!((matrix.macro_f1() - (2.0 / 3.0 + 0.5 + 0.75) / 3.0).abs() < 1e-15);
+    }
+
+    #[test]
+    fn test_empty_classes_score_zero() {
+        let matrix = ConfusionMatrix::new(&[0, 0], &[0, 0], 2);
+        assert_eq!(matrix.precision(1), 0.0);
+        assert_eq!(matrix.recall(1), 0.0);
+        assert_eq!(matrix.f1(1), 0.0);
+        assert_eq!(matrix.f1(0), 1.0);
+        // out-of-range classes are dropped
+        assert_eq!(ConfusionMatrix::new(&[5], &[0], 2).total(), 0);
+        // and read as empty, not as some other cell
+        let matrix = ConfusionMatrix::new(&[0, 1, 2, 0], &[0, 1, 2, 1], 3);
+        assert_eq!(matrix.get(0, 3), 0);
+        assert_eq!(matrix.get(3, 0), 0);
+        assert_eq!(matrix.row(3), []);
+        assert_eq!(matrix.precision(3), 0.0);
+        assert_eq!(matrix.recall(3), 0.0);
+        assert_eq!(matrix.f1(3), 0.0);
+    }
+
+    #[test]
+    fn test_roc_auc() {
+        assert_eq!(
+            roc_auc(&[0.1, 0.4, 0.35, 0.8], &[false, false, true, true]),
+            Some(0.75)
+        );
+        assert_eq!(roc_auc(&[0.1, 0.9], &[false, true]), Some(1.0));
+        assert_eq!(roc_auc(&[0.1, 0.9], &[true, false]), Some(0.0));
+        // every score tied: no better than chance
+        assert_eq!(roc_auc(&[0.5; 4], &[true, false, true, false]), Some(0.5));
+        // a positive tied with one of two negatives above it
+        assert_eq!(roc_auc(&[0.2, 0.5, 0.5], &[false, false, true]), Some(0.75));
+        assert_eq!(roc_auc(&[0.2, 0.5], &[true, true]), None);
+        assert_eq!(roc_auc(&[f64::NAN, 0.5], &[true, false]), None);
+        assert_eq!(roc_auc(&[0.1, f64::INFINITY], &[false, true]), None);
+    }
+
+    #[test]
+    fn test_regression_metrics() {
+        let actual = [3.0, -0.5, 2.0, 7.0];
+        let predicted = [2.5, 0.0, 2.0, 8.0];
+        assert_eq!(mean_absolute_error(&predicted, &actual), 0.5);
+        // the scikit-learn docs example
+        assert!((r_squared(&predicted, &actual) - 0.9486081370449679).abs() < 1e-15);
+        assert_eq!(r_squared(&actual, &actual), 1.0);
+        assert_eq!(r_squared(&[1.0, 1.0], &[2.0, 2.0]), 0.0);
+        assert_eq!(r_squared(&[2.0, 2.0], &[2.0, 2.0]), 1.0);
+        assert_eq!(mean_absolute_error(&[], &[]), 0.0);
+    }
+}
@@ -0,0 +1,458 @@
+// Dense multi-layer perceptron built out of `Unit` graphs.
+//
+// Parameters are stored as plain numbers, layer by layer and neuron by neuron
+// (weights then bias), the same order as `checkpoint`. Every forward pass builds
+// a fresh graph with one leaf per parameter use, labelled uniquely, so grads are
+// read back with `Unit::leaf_grads` after `traverse_backward`. Inputs and targets
+// enter the graph as constants and never show up among the grads.
+//
+// Losses are per sample and averaged over a batch by `mean_loss`; `Mlp::loss`
+// and `Trainer` both go through it, with `squared_error` by default.
+use crate::checkpoint::{Checkpoint, CheckpointError, LayerShape, ScalarType};
+use crate::core::Unit;
+use crate::data::Sample;
+use crate::rand::{Init, Rng};
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Activation {
+    Tanh,
+    Linear,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layer {
+    pub shape: LayerShape,
+    pub activation: Activation,
+    pub params: Vec<f64>,
+    // one label per parameter, `l<layer>.n<neuron>.w<input>` or `l<layer>.n<neuron>.b`
+    labels: Vec<&'static str>,
+}
+
+impl Layer {
+    fn new(index: usize, shape: LayerShape, activation: Activation, params: Vec<f64>) -> Self {
+        let mut labels = Vec::with_capacity(shape.param_count());
+        for neuron in 0..shape.outputs {
+            for input in 0..shape.inputs {
+                labels.push(intern(format!("l{}.n{}.w{}", index, neuron, input)));
+            }
+            labels.push(intern(format!("l{}.n{}.b", index, neuron)));
+        }
+        Layer {
+            shape,
+            activation,
+            params,
+            labels,
+        }
+    }
+
+    pub fn forward(&self, inputs: &[Unit]) -> Vec<Unit> {
+        let stride = self.shape.inputs + 1;
+        (0..self.shape.outputs)
+            .map(|neuron| {
+                let offset = neuron * stride;
+                let bias = offset + self.shape.inputs;
+                let mut sum = Unit::new(self.params[bias], self.labels[bias]);
+                for (idx, input) in inputs.iter().enumerate() {
+                    let weight = Unit::new(self.params[offset + idx], self.labels[offset + idx]);
+                    sum = weight * input.clone() + sum;
+                }
+                match self.activation {
+                    Activation::Tanh => sum.tanh(),
+                    Activation::Linear => sum,
+                }
+            })
+            .collect()
+    }
+
+    pub fn labels(&self) -> &[&'static str] {
+        &self.labels
+    }
+}
+
+// Anything a `Trainer` can fit: a forward pass that builds `Unit` graphs whose
+// leaves are labelled like the parameters handed to an optimizer.
+pub trait Model {
+    fn forward(&self, inputs: &[f64]) -> Vec<Unit>;
+
+    fn params(&self) -> Vec<(&'static str, f64)>;
+
+    fn params_mut(&mut self) -> Vec<(&'static str, &mut f64)>;
+
+    // The parameters weight penalties and decay are meant for: all of them
+    // unless the model says otherwise. `Mlp` leaves out its biases.
+    fn weights(&self) -> Vec<(&'static str, f64)> {
+        self.params()
+    }
+}
+
+// The labels of `model.weights()`, for an optimizer's `decayed`.
+pub fn weight_labels<M: Model + ?Sized>(model: &M) -> BTreeSet<&'static str> {
+    model
+        .weights()
+        .into_iter()
+        .map(|(label, _)| label)
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mlp {
+    pub layers: Vec<Layer>,
+}
+
+impl Mlp {
+    // `sizes` lists the width of every layer, inputs first. Hidden layers use
+    // tanh and the output layer `output`. Weights are Xavier-uniform from a
+    // generator seeded by `seed`.
+    pub fn new(sizes: &[usize], output: Activation, seed: u64) -> Self {
+        Mlp::with_init(sizes, output, Init::XavierUniform, &mut Rng::new(seed))
+    }
+
+    // Weights are drawn from `init`, biases start at zero.
+    pub fn with_init(sizes: &[usize], output: Activation, init: Init, rng: &mut Rng) -> Self {
+        let count = sizes.len().saturating_sub(1);
+        let layers = (0..count)
+            .map(|index| {
+                let shape = LayerShape::new(sizes[index], sizes[index + 1]);
+                let mut params = Vec::with_capacity(shape.param_count());
+                for _ in 0..shape.outputs {
+                    for _ in 0..shape.inputs {
+                        params.push(init.sample(rng, shape.inputs, shape.outputs));
+                    }
+                    params.push(0.0);
+                }
+                let activation = if index + 1 == count {
+                    output
+                } else {
+                    Activation::Tanh
+                };
+                Layer::new(index, shape, activation, params)
+            })
+            .collect();
+        Mlp { layers }
+    }
+
+    pub fn forward(&self, inputs: &[f64]) -> Vec<Unit> {
+        let mut units: Vec<Unit> = inputs.iter().map(|x| Unit::constant(*x)).collect();
+        for layer in self.layers.iter() {
+            units = layer.forward(&units);
+        }
+        units
+    }
+
+    pub fn predict(&self, inputs: &[f64]) -> Vec<f64> {
+        self.forward(inputs).iter().map(|unit| unit.value).collect()
+    }
+
+    // Mean squared error over `samples`, as a graph to call `traverse_backward` on.
+    pub fn loss(&self, samples: &[Sample]) -> Unit {
+        mean_loss(self, squared_error, samples)
+    }
+
+    // Plain gradient descent on the grads returned by `Unit::leaf_grads`.
+    pub fn step(&mut self, grads: &BTreeMap<&'static str, f64>, learning_rate: f64) {
+        for layer in self.layers.iter_mut() {
+            for (param, label) in layer.params.iter_mut().zip(layer.labels.iter()) {
+                if let Some(grad) = grads.get(label) {
+                    *param -= learning_rate * grad;
+                }
+            }
+        }
+    }
+
+    // Euclidean norm of each layer's slice of `grads`.
+    pub fn layer_grad_norms(&self, grads: &BTreeMap<&'static str, f64>) -> Vec<f64> {
+        self.layers
+            .iter()
+            .map(|layer| {
+                let sum: f64 = layer
+                    .labels
+                    .iter()
+                    .filter_map(|label| grads.get(label))
+                    .map(|grad| grad * grad)
+                    .sum();
+                libm::sqrt(sum)
+            })
+            .collect()
+    }
+
+    pub fn shapes(&self) -> Vec<LayerShape> {
+        self.layers.iter().map(|layer| layer.shape).collect()
+    }
+
+    // The parameters in checkpoint order, stored as `scalar_type`.
+    pub fn to_checkpoint(&self, scalar_type: ScalarType) -> Checkpoint {
+        Checkpoint {
+            scalar_type,
+            layers: self.shapes(),
+            params: self
+                .layers
+                .iter()
+                .flat_map(|layer| layer.params.iter().copied())
+                .collect(),
+        }
+    }
+
+    // Checkpoints keep no activations: hidden layers use tanh and the output
+    // layer `output`, as with `Mlp::new`.
+    pub fn from_checkpoint(
+        checkpoint: Checkpoint,
+        output: Activation,
+    ) -> Result<Self, CheckpointError> {
+        // the fields are public, so they may not agree with each other
+        let Checkpoint { layers, params, .. } =
+            Checkpoint::new(checkpoint.scalar_type, checkpoint.layers, checkpoint.params)?;
+        for (index, pair) in layers.windows(2).enumerate() {
+            if pair[1].inputs != pair[0].outputs {
+                return Err(CheckpointError::LayerMismatch { layer: index + 1 });
+            }
+        }
+        let count = layers.len();
+        let mut params = params.into_iter();
+        let layers = layers
+            .into_iter()
+            .enumerate()
+            .map(|(index, shape)| {
+                let activation = if index + 1 == count {
+                    output
+                } else {
+                    Activation::Tanh
+                };
+                let layer_params = params.by_ref().take(shape.param_count()).collect();
+                Layer::new(index, shape, activation, layer_params)
+            })
+            .collect();
+        Ok(Mlp { layers })
+    }
+}
+
+impl Model for Mlp {
+    fn forward(&self, inputs: &[f64]) -> Vec<Unit> {
+        Mlp::forward(self, inputs)
+    }
+
+    fn params(&self) -> Vec<(&'static str, f64)> {
+        self.layers
+            .iter()
+            .flat_map(|layer| {
+                layer
+                    .labels
+                    .iter()
+                    .copied()
+                    .zip(layer.params.iter().copied())
+            })
+            .collect()
+    }
+
+    fn params_mut(&mut self) -> Vec<(&'static str, &mut f64)> {
+        self.layers
+            .iter_mut()
+            .flat_map(|layer| layer.labels.iter().copied().zip(layer.params.iter_mut()))
+            .collect()
+    }
+
+    fn weights(&self) -> Vec<(&'static str, f64)> {
+        let mut params = self.params();
+        params.retain(|(label, _)| !label.ends_with(".b"));
+        params
+    }
+}
+
+// Loss of one sample, from the model outputs and the targets.
+pub type Loss = fn(Vec<Unit>, &[f64]) -> Unit;
+
+// Squared error averaged over the outputs.
+pub fn squared_error(outputs: Vec<Unit>, targets: &[f64]) -> Unit {
+    let count = outputs.len().min(targets.len());
+    let mut total = Unit::constant(0.0);
+    for (output, target) in outputs.into_iter().zip(targets.iter()) {
+        let diff = output + Unit::constant(-target);
+        total = diff.clone() * diff + total;
+    }
+    total * Unit::constant(1.0 / count.max(1) as f64)
+}
+
+// `loss` averaged over `samples`, as one graph.
+pub fn mean_loss<M: Model + ?Sized>(model: &M, loss: Loss, samples: &[Sample]) -> Unit {
+    let mut total = Unit::constant(0.0);
+    for (inputs, targets) in samples.iter() {
+        total = loss(model.forward(inputs), targets) + total;
+    }
+    total * Unit::constant(1.0 / samples.len().max(1) as f64)
+}
+
+// Parameter labels are `&'static str` like every other `Unit` label. With
+// `std` each distinct one is leaked once and shared by every model built
+// after, so memory grows with the largest shape in use, not with the number of
+// models; without it every model leaks its own.
+#[cfg(feature = "std")]
+pub(crate) fn intern(label: String) -> &'static str {
+    static LABELS: std::sync::Mutex<BTreeSet<&'static str>> =
+        std::sync::Mutex::new(BTreeSet::new());
+    // a panic elsewhere can't leave the set half-updated, so poisoning is moot
+    let mut labels = LABELS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    match labels.get(label.as_str()) {
+        Some(interned) => interned,
+        None => {
+            let interned: &'static str = Box::leak(label.into_boxed_str());
+            labels.insert(interned);
+            interned
+        }
+    }
+}
+
+#[cfg(not(feature = "std"))]
+pub(crate) fn intern(label: String) -> &'static str {
+    Box::leak(label.into_boxed_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate::xor_corners;
+    use alloc::vec;
+
+    #[test]
+    fn test_shapes_and_labels() {
+        let mlp = Mlp::new(&[2, 3, 1], Activation::Tanh, 7);
+        assert_eq!(
+            mlp.shapes(),
+            vec![LayerShape::new(2, 3), LayerShape::new(3, 1)]
+        );
+        assert_eq!(mlp.layers[0].params.len(), 9);
+        assert_eq!(
+            mlp.layers[0].labels()[..3],
+            ["l0.n0.w0", "l0.n0.w1", "l0.n0.b"]
+        );
+        assert_eq!(mlp.layers[1].activation, Activation::Tanh);
+        assert_eq!(mlp.predict(&[0.5, -0.5]).len(), 1);
+        // the same seed gives the same model
+        assert_eq!(mlp, Mlp::new(&[2, 3, 1], Activation::Tanh, 7));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_models_share_labels() {
+        let a = Mlp::new(&[2, 3, 1], Activation::Tanh, 1);
+        let b = Mlp::new(&[2, 5, 2], Activation::Linear, 2);
+        for (x, y) in a.layers[0].labels().iter().zip(b.layers[0].labels()) {
+            assert!(core::ptr::eq(*x, *y));
+        }
+        assert!(core::ptr::eq(
+            a.layers[1].labels()[0],
+            b.layers[1].labels()[0]
+        ));
+    }
+
+    #[test]
+    fn test_checkpoint_round_trip() {
+        let mut mlp = Mlp::new(&[2, 4, 1], Activation::Linear, 3);
+        let samples = xor_corners().into_samples();
+        for _ in 0..20 {
+            let mut loss = mlp.loss(&samples);
+            loss.grad = 1.0;
+            loss.traverse_backward();
+            mlp.step(&loss.leaf_grads(), 0.2);
+        }
+        let bytes = mlp.to_checkpoint(ScalarType::F64).to_bytes();
+        let loaded = Checkpoint::from_bytes(&bytes).unwrap();
+        assert_eq!(Mlp::from_checkpoint(loaded, Activation::Linear), Ok(mlp));
+    }
+
+    #[test]
+    fn test_from_checkpoint_checks_layers_chain() {
+        let mut checkpoint =
+            Mlp::new(&[2, 3, 1], Activation::Tanh, 1).to_checkpoint(ScalarType::F64);
+        checkpoint.layers[1] = LayerShape::new(2, 1);
+        checkpoint.params.pop();
+        assert_eq!(
+            Mlp::from_checkpoint(checkpoint.clone(), Activation::Tanh),
+            Err(CheckpointError::LayerMismatch { layer: 1 })
+        );
+        checkpoint.params.pop();
+        assert!(matches!(
+            Mlp::from_checkpoint(checkpoint.clone(), Activation::Tanh),
+            Err(CheckpointError::ParamCountMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_params_mut_follow_labels() {
+        let mut mlp = Mlp::new(&[1, 2, 1], Activation::Linear, 3);
+        let params = mlp.params_mut();
+        assert_eq!(params.len(), 7);
+        assert_eq!(params[1].0, "l0.n0.b");
+        assert_eq!(params[6].0, "l1.n0.b");
+        for (_, param) in params {
+            *param = 0.5;
+        }
+        assert_eq!(mlp.layers[1].params, vec![0.5; 3]);
+        assert_eq!(mlp.params()[6], ("l1.n0.b", 0.5));
+    }
+
+    #[test]
+    fn test_weights_leave_out_biases() {
+        let mlp = Mlp::new(&[2, 2, 1], Activation::Tanh, 1);
+        let weights = mlp.weights();
+        assert_eq!(weights.len(), 6);
+        assert_eq!(weights[2], mlp.params()[3]);
+        let labels = weight_labels(&mlp);
+        assert!(labels.contains("l1.n0.w1"));
+        assert!(!labels.contains("l1.n0.b"));
+    }
+
+    #[test]
+    fn test_with_init() {
+        let mlp = Mlp::with_init(
+            &[4, 2],
+            Activation::Linear,
+            Init::HeUniform,
+            &mut Rng::new(3),
+        );
+        let params = &mlp.layers[0].params;
+        // biases start at zero, weights within sqrt(6 / fan_in)
+        assert_eq!((params[4], params[9]), (0.0, 0.0));
+        assert!(params
+            .iter()
+            .all(|weight| weight.abs() <= libm::sqrt(6.0 / 4.0)));
+        assert!(params.iter().any(|weight| *weight != 0.0));
+    }
+
+    #[test]
+    fn test_forward_matches_by_hand() {
+        let mut mlp = Mlp::new(&[2, 1], Activation::Linear, 0);
+        mlp.layers[0].params = vec![2.0, -1.0, 0.5];
+        assert_eq!(mlp.predict(&[3.0, 4.0]), vec![2.5]);
+    }
+
+    #[test]
+    fn test_grads_cover_every_param() {
+        let mlp = Mlp::new(&[2, 2, 1], Activation::Tanh, 1);
+        let mut loss = mlp.loss(xor_corners().samples());
+        loss.grad = 1.0;
+        loss.traverse_backward();
+        let grads = loss.leaf_grads();
+        assert_eq!(grads.len(), 9);
+        assert_eq!(mlp.layer_grad_norms(&grads).len(), 2);
+    }
+
+    #[test]
+    fn test_training_reduces_loss() {
+        let mut mlp = Mlp::new(&[2, 4, 1], Activation::Tanh, 3);
+        let samples = xor_corners().into_samples();
+        let initial = mlp.loss(&samples).value;
+        for _ in 0..200 {
+            let mut loss = mlp.loss(&samples);
+            loss.grad = 1.0;
+            loss.traverse_backward();
+            mlp.step(&loss.leaf_grads(), 0.2);
+        }
+        assert!(mlp.loss(&samples).value < initial / 2.0);
+    }
+}
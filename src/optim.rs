@@ -0,0 +1,333 @@
+// Optimizers update parameters in place from the grads of `Unit::leaf_grads`.
+//
+// Parameters come as `(label, value)` pairs, as `Model::params_mut` returns
+// them, and grads are matched by label. A parameter with no grad, one the loss
+// didn't reach, is left alone. Any per-parameter state is keyed by label too.
+// Grads can be clipped in between, to keep an exploding step in check. Clip
+// limits are taken by size, so -1 clips like 1 instead of flipping the grads,
+// and a NaN limit clips nothing.
+//
+// `weight_decay` is decoupled, as in AdamW: each step first shrinks a parameter
+// by `learning_rate * weight_decay` of itself, apart from its grad, instead of
+// adding an L2 term to the loss (see `regularize` for that). It reaches every
+// parameter unless `decayed` lists the ones it should, such as the
+// `nn::weight_labels` of a model to leave its biases alone.
+use alloc::collections::{BTreeMap, BTreeSet};
+use libm::sqrt;
+
+pub type Grads = BTreeMap<&'static str, f64>;
+
+pub trait Optimizer {
+    fn step(&mut self, params: &mut [(&'static str, &mut f64)], grads: &Grads);
+
+    fn learning_rate(&self) -> f64;
+
+    // For schedulers, between steps.
+    fn set_learning_rate(&mut self, learning_rate: f64);
+}
+
+// Gradient descent, with classical momentum when `momentum` is above zero.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sgd {
+    pub learning_rate: f64,
+    pub momentum: f64,
+    pub weight_decay: f64,
+    pub decayed: Option<BTreeSet<&'static str>>,
+    velocities: BTreeMap<&'static str, f64>,
+}
+
+impl Sgd {
+    pub fn new(learning_rate: f64) -> Self {
+        Sgd::with_momentum(learning_rate, 0.0)
+    }
+
+    pub fn with_momentum(learning_rate: f64, momentum: f64) -> Self {
+        Sgd {
+            learning_rate,
+            momentum,
+            weight_decay: 0.0,
+            decayed: None,
+            velocities: BTreeMap::new(),
+        }
+    }
+}
+
+impl Optimizer for Sgd {
+    fn step(&mut self, params: &mut [(&'static str, &mut f64)], grads: &Grads) {
+        for (label, param) in params.iter_mut() {
+            let Some(grad) = grads.get(label) else {
+                continue;
+            };
+            if decays(&self.decayed, label) {
+                **param -= self.learning_rate * self.weight_decay * **param;
+            }
+            let velocity = self.velocities.entry(label).or_insert(0.0);
+            *velocity = self.momentum * *velocity - self.learning_rate * grad;
+            **param += *velocity;
+        }
+    }
+
+    fn learning_rate(&self) -> f64 {
+        self.learning_rate
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f64) {
+        self.learning_rate = learning_rate;
+    }
+}
+
+// Adam, or AdamW with a `weight_decay`. Bias correction counts the steps taken
+// by the optimizer, not per parameter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Adam {
+    pub learning_rate: f64,
+    pub beta1: f64,
+    pub beta2: f64,
+    pub epsilon: f64,
+    pub weight_decay: f64,
+    pub decayed: Option<BTreeSet<&'static str>>,
+    steps: i32,
+    // first and second moment estimates
+    moments: BTreeMap<&'static str, (f64, f64)>,
+}
+
+impl Adam {
+    // Betas 0.9 and 0.999, epsilon 1e-8 and no weight decay.
+    pub fn new(learning_rate: f64) -> Self {
+        Adam::with_weight_decay(learning_rate, 0.0)
+    }
+
+    pub fn with_weight_decay(learning_rate: f64, weight_decay: f64) -> Self {
+        Adam {
+            learning_rate,
+            beta1: 0.9,
+            beta2: 0.999,
+            epsilon: 1e-8,
+            weight_decay,
+            decayed: None,
+            steps: 0,
+            moments: BTreeMap::new(),
+        }
+    }
+}
+
+impl Optimizer for Adam {
+    fn step(&mut self, params: &mut [(&'static str, &mut f64)], grads: &Grads) {
+        self.steps = self.steps.saturating_add(1);
+        let first_correction = 1.0 - self.beta1.powi(self.steps);
+        let second_correction = 1.0 - self.beta2.powi(self.steps);
+        for (label, param) in params.iter_mut() {
+            let Some(grad) = grads.get(label) else {
+                continue;
+            };
+            if decays(&self.decayed, label) {
+                **param -= self.learning_rate * self.weight_decay * **param;
+            }
+            let (first, second) = self.moments.entry(label).or_insert((0.0, 0.0));
+            *first = self.beta1 * *first + (1.0 - self.beta1) * grad;
+            *second = self.beta2 * *second + (1.0 - self.beta2) * grad * grad;
+            let first = *first / first_correction;
+            let second = *second / second_correction;
+            **param -= self.learning_rate * first / (sqrt(second) + self.epsilon);
+        }
+    }
+
+    fn learning_rate(&self) -> f64 {
+        self.learning_rate
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f64) {
+        self.learning_rate = learning_rate;
+    }
+}
+
+fn decays(decayed: &Option<BTreeSet<&'static str>>, label: &str) -> bool {
+    decayed
+        .as_ref()
+        .is_none_or(|decayed| decayed.contains(label))
+}
+
+// Global L2 norm over all grads.
+pub fn grad_norm(grads: &Grads) -> f64 {
+    sqrt(grads.values().map(|grad| grad * grad).sum())
+}
+
+// Scales all grads down together so their global norm is at most `max_norm`,
+// keeping their direction, and returns the norm from before.
+pub fn clip_grad_norm(grads: &mut Grads, max_norm: f64) -> f64 {
+    let norm = grad_norm(grads);
+    let max_norm = max_norm.abs();
+    if norm > max_norm {
+        let scale = max_norm / norm;
+        grads.values_mut().for_each(|grad| *grad *= scale);
+    }
+    norm
+}
+
+// Clamps each grad to [-limit, limit] and returns the norm from before.
+pub fn clip_grad_value(grads: &mut Grads, limit: f64) -> f64 {
+    let norm = grad_norm(grads);
+    let limit = limit.abs();
+    if !limit.is_nan() {
+        grads
+            .values_mut()
+            .for_each(|grad| *grad = grad.clamp(-limit, limit));
+    }
+    norm
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Clip {
+    Norm(f64),
+    Value(f64),
+}
+
+impl Clip {
+    // Clips `grads` and returns their norm from before.
+    pub fn apply(&self, grads: &mut Grads) -> f64 {
+        match *self {
+            Clip::Norm(max_norm) => clip_grad_norm(grads, max_norm),
+            Clip::Value(limit) => clip_grad_value(grads, limit),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sgd() {
+        let (mut a, mut b) = (1.0, 2.0);
+        let grads = Grads::from([("a", 0.5)]);
+        let mut sgd = Sgd::new(0.1);
+        sgd.step(&mut [("a", &mut a), ("b", &mut b)], &grads);
+        assert_eq!((a, b), (0.95, 2.0));
+        sgd.set_learning_rate(1.0);
+        assert_eq!(sgd.learning_rate(), 1.0);
+    }
+
+    #[test]
+    fn test_momentum_accumulates() {
+        let mut x = 0.0;
+        let grads = Grads::from([("x", 1.0)]);
+        let mut sgd = Sgd::with_momentum(1.0, 0.5);
+        sgd.step(&mut [("x", &mut x)], &grads);
+        assert_eq!(x, -1.0);
+        // the velocity is now -1 * 0.5 - 1
+        sgd.step(&mut [("x", &mut x)], &grads);
+        assert_eq!(x, -2.5);
+    }
+
+    #[test]
+    fn test_sgd_weight_decay() {
+        let (mut a, mut b) = (2.0, 4.0);
+        let mut sgd = Sgd::new(0.5);
+        sgd.weight_decay = 0.25;
+        // b has no grad and is left alone, decay included
+        sgd.step(
+            &mut [("a", &mut a), ("b", &mut b)],
+            &Grads::from([("a", 1.0)]),
+        );
+        assert_eq!((a, b), (2.0 - 0.25 - 0.5, 4.0));
+
+        // only the listed parameters decay
+        let (mut a, mut b) = (2.0, 4.0);
+        sgd.decayed = Some(BTreeSet::from(["b"]));
+        sgd.step(
+            &mut [("a", &mut a), ("b", &mut b)],
+            &Grads::from([("a", 1.0), ("b", 1.0)]),
+        );
+        assert_eq!((a, b), (2.0 - 0.5, 4.0 - 0.5 - 0.5));
+    }
+
+    #[test]
+    fn test_adam() {
+        let (mut a, mut b) = (1.0, 1.0);
+        let grads = Grads::from([("a", 4.0), ("b", -0.001)]);
+        let mut adam = Adam::new(0.1);
+        adam.epsilon = 0.0;
+        // the first corrected step is the learning rate times the grad's sign,
+        // whatever its size
+        adam.step(&mut [("a", &mut a), ("b", &mut b)], &grads);
+        assert!((a - 0.9).abs() < 1e-12 && (b - 1.1).abs() < 1e-12);
+
+        // hand-computed second step for a grad of 4 then 2
+        let mut x = 0.0;
+        let mut adam = Adam::new(0.1);
+        adam.step(&mut [("x", &mut x)], &Grads::from([("x", 4.0)]));
+        adam.step(&mut [("x", &mut x)], &Grads::from([("x", 2.0)]));
+        let first = (0.9 * 0.4 + 0.2) / (1.0 - 0.81);
+        let second = (0.999 * 0.016 + 0.004) / (1.0 - 0.998001);
+        let expected = -0.1 * 4.0 / (4.0 + 1e-8) - 0.1 * first / (sqrt(second) + 1e-8);
+        assert!((x - expected).abs() < 1e-12, "{} != {}", x, expected);
+    }
+
+    #[test]
+    fn test_adamw_decouples_decay() {
+        // a zero grad leaves only the decay: w * (1 - lr * wd) per step
+        let mut w = 2.0;
+        let mut adam = Adam::with_weight_decay(0.1, 0.5);
+        for _ in 0..3 {
+            adam.step(&mut [("w", &mut w)], &Grads::from([("w", 0.0)]));
+        }
+        assert!((w - 2.0 * 0.95f64.powi(3)).abs() < 1e-12);
+
+        // the decay doesn't go through the moments, unlike an L2 term in the
+        // grad, which Adam would normalize to a full learning-rate step
+        let mut w = 2.0;
+        let mut adam = Adam::with_weight_decay(0.1, 0.5);
+        adam.epsilon = 0.0;
+        adam.step(&mut [("w", &mut w)], &Grads::from([("w", 1.0)]));
+        assert!((w - (2.0 * 0.95 - 0.1)).abs() < 1e-12);
+        adam.set_learning_rate(0.2);
+        assert_eq!(adam.learning_rate(), 0.2);
+
+        let mut w = 2.0;
+        let mut adam = Adam::with_weight_decay(0.1, 0.5);
+        adam.decayed = Some(BTreeSet::new());
+        adam.step(&mut [("w", &mut w)], &Grads::from([("w", 0.0)]));
+        assert_eq!(w, 2.0);
+    }
+
+    #[test]
+    fn test_grad_norm() {
+        assert_eq!(grad_norm(&Grads::new()), 0.0);
+        assert_eq!(grad_norm(&Grads::from([("a", 3.0), ("b", -4.0)])), 5.0);
+    }
+
+    #[test]
+    fn test_clip_grad_norm() {
+        let mut grads = Grads::from([("a", 3.0), ("b", -4.0)]);
+        assert_eq!(clip_grad_norm(&mut grads, 2.5), 5.0);
+        assert_eq!(grads, Grads::from([("a", 1.5), ("b", -2.0)]));
+        assert_eq!(grad_norm(&grads), 2.5);
+        // already within the limit
+        assert_eq!(Clip::Norm(10.0).apply(&mut grads), 2.5);
+        assert_eq!(grads, Grads::from([("a", 1.5), ("b", -2.0)]));
+    }
+
+    #[test]
+    fn test_clip_grad_value() {
+        let mut grads = Grads::from([("a", 3.0), ("b", -4.0), ("c", 0.5)]);
+        let norm = Clip::Value(1.0).apply(&mut grads);
+        assert_eq!(norm, sqrt(25.25));
+        assert_eq!(grads, Grads::from([("a", 1.0), ("b", -1.0), ("c", 0.5)]));
+    }
+
+    #[test]
+    fn test_clip_limits_by_size() {
+        let original = Grads::from([("a", 3.0), ("b", -4.0)]);
+        let mut grads = original.clone();
+        Clip::Norm(-2.5).apply(&mut grads);
+        assert_eq!(grads, Grads::from([("a", 1.5), ("b", -2.0)]));
+        let mut grads = original.clone();
+        Clip::Value(-1.0).apply(&mut grads);
+        assert_eq!(grads, Grads::from([("a", 1.0), ("b", -1.0)]));
+        for clip in [Clip::Norm(f64::NAN), Clip::Value(f64::NAN)] {
+            let mut grads = original.clone();
+            assert_eq!(clip.apply(&mut grads), 5.0);
+            assert_eq!(grads, original);
+        }
+    }
+}
@@ -0,0 +1,330 @@
+use crate::core::Unit;
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use core::fmt;
+
+// Parses expressions such as `tanh(a*b + c) * d` into a `Unit` graph.
+//
+// Grammar, from lowest to highest precedence:
+//   expr    := term (('+' | '-') term)*
+//   term    := unary ('*' unary)*
+//   unary   := '-' unary | primary
+//   primary := number | ident | ident '(' expr ')' | '(' expr ')'
+//
+// Identifiers are looked up in `bindings` and become leaves carrying the binding's
+// label; numeric literals become `Unit::constant`s. `a - b` is built as
+// `a + -1 * b` since the engine has no subtraction op.
+pub fn parse(input: &str, bindings: &BTreeMap<&'static str, f64>) -> Result<Unit, ParseError> {
+    parse_with(input, &|name| {
+        bindings
+            .get_key_value(name)
+            .map(|(label, value)| Unit::new(*value, label))
+    })
+}
+
+// Like `parse`, but identifiers are resolved by `lookup`, which may return any
+// graph (e.g. a previously built expression) rather than just a leaf.
+pub fn parse_with(input: &str, lookup: &dyn Fn(&str) -> Option<Unit>) -> Result<Unit, ParseError> {
+    let mut parser = Parser {
+        input,
+        pos: 0,
+        lookup,
+    };
+    let unit = parser.expr()?;
+    parser.skip_whitespace();
+    match parser.peek() {
+        None => Ok(unit),
+        Some(c) => Err(parser.error(ParseErrorKind::UnexpectedChar(c))),
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParseError {
+    // Byte offset into the input where the error was detected.
+    pub position: usize,
+    pub kind: ParseErrorKind,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ParseErrorKind {
+    UnexpectedChar(char),
+    UnexpectedEnd,
+    InvalidNumber(String),
+    UnknownVariable(String),
+    UnknownFunction(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ParseErrorKind::UnexpectedChar(c) => write!(f, "unexpected `{}`", c)?,
+            ParseErrorKind::UnexpectedEnd => write!(f, "unexpected end of input")?,
+            ParseErrorKind::InvalidNumber(n) => write!(f, "invalid number `{}`", n)?,
+            ParseErrorKind::UnknownVariable(v) => write!(f, "unknown variable `{}`", v)?,
+            ParseErrorKind::UnknownFunction(n) => write!(f, "unknown function `{}`", n)?,
+        }
+        write!(f, " at position {}", self.position)
+    }
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+    lookup: &'a dyn Fn(&str) -> Option<Unit>,
+}
+
+impl<'a> Parser<'a> {
+    fn expr(&mut self) -> Result<Unit, ParseError> {
+        let mut lhs = self.term()?;
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some('+') => {
+                    self.bump();
+                    lhs = lhs + self.term()?;
+                }
+                Some('-') => {
+                    self.bump();
+                    lhs = lhs + Unit::constant(-1.0) * self.term()?;
+                }
+                _ => return Ok(lhs),
+            }
+        }
+    }
+
+    fn term(&mut self) -> Result<Unit, ParseError> {
+        let mut lhs = self.unary()?;
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some('*') => {
+                    self.bump();
+                    lhs = lhs * self.unary()?;
+                }
+                _ => return Ok(lhs),
+            }
+        }
+    }
+
+    fn unary(&mut self) -> Result<Unit, ParseError> {
+        self.skip_whitespace();
+        if self.peek() == Some('-') {
+            self.bump();
+            let operand = self.unary()?;
+            if operand.is_constant() {
+                return Ok(Unit::constant(-operand.value));
+            }
+            return Ok(Unit::constant(-1.0) * operand);
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Unit, ParseError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('(') => {
+                self.bump();
+                let inner = self.expr()?;
+                self.expect(')')?;
+                Ok(inner)
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => self.number(),
+            Some(c) if c.is_alphabetic() || c == '_' => self.identifier(),
+            Some(c) => Err(self.error(ParseErrorKind::UnexpectedChar(c))),
+            None => Err(self.error(ParseErrorKind::UnexpectedEnd)),
+        }
+    }
+
+    fn number(&mut self) -> Result<Unit, ParseError> {
+        let start = self.pos;
+        self.eat_while(|c| c.is_ascii_digit() || c == '.');
+        if matches!(self.peek(), Some('e') | Some('E')) {
+            self.bump();
+            if matches!(self.peek(), Some('+') | Some('-')) {
+                self.bump();
+            }
+            self.eat_while(|c| c.is_ascii_digit());
+        }
+        let text = &self.input[start..self.pos];
+        text.parse::<f64>()
+            .map(Unit::constant)
+            .map_err(|_| ParseError {
+                position: start,
+                kind: ParseErrorKind::InvalidNumber(text.to_string()),
+            })
+    }
+
+    fn identifier(&mut self) -> Result<Unit, ParseError> {
+        let start = self.pos;
+        self.eat_while(|c| c.is_alphanumeric() || c == '_');
+        let name = &self.input[start..self.pos];
+        self.skip_whitespace();
+        if self.peek() == Some('(') {
+            return match name {
+                "tanh" => {
+                    self.bump();
+                    let argument = self.expr()?;
+                    self.expect(')')?;
+                    Ok(argument.tanh())
+                }
+                _ => Err(ParseError {
+                    position: start,
+                    kind: ParseErrorKind::UnknownFunction(name.to_string()),
+                }),
+            };
+        }
+        match (self.lookup)(name) {
+            Some(unit) => Ok(unit),
+            None => Err(ParseError {
+                position: start,
+                kind: ParseErrorKind::UnknownVariable(name.to_string()),
+            }),
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), ParseError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.bump();
+                Ok(())
+            }
+            Some(c) => Err(self.error(ParseErrorKind::UnexpectedChar(c))),
+            None => Err(self.error(ParseErrorKind::UnexpectedEnd)),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) {
+        if let Some(c) = self.peek() {
+            self.pos += c.len_utf8();
+        }
+    }
+
+    fn eat_while(&mut self, predicate: impl Fn(char) -> bool) {
+        while let Some(c) = self.peek() {
+            if !predicate(c) {
+                break;
+            }
+            self.bump();
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.eat_while(char::is_whitespace);
+    }
+
+    fn error(&self, kind: ParseErrorKind) -> ParseError {
+        ParseError {
+            position: self.pos,
+            kind,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Op;
+
+    fn bindings() -> BTreeMap<&'static str, f64> {
+        let mut bindings = BTreeMap::new();
+        bindings.insert("a", 2.0);
+        bindings.insert("b", -3.0);
+        bindings.insert("c", 10.0);
+        bindings.insert("d", 0.5);
+        bindings
+    }
+
+    #[test]
+    fn test_parse_matches_hand_built_graph() {
+        let a = Unit::new(2.0f64, "a");
+        let b = Unit::new(-3.0f64, "b");
+        let c = Unit::new(10.0f64, "c");
+        let d = Unit::new(0.5f64, "d");
+        let expected = (a * b + c).tanh() * d;
+        let parsed = parse("tanh(a*b + c) * d", &bindings()).unwrap();
+        assert_eq!(parsed, expected);
+    }
+
+    #[test]
+    fn test_precedence_and_parentheses() {
+        let b = bindings();
+        assert_eq!(parse("a + b * c", &b).unwrap().value, -28.0);
+        assert_eq!(parse("(a + b) * c", &b).unwrap().value, -10.0);
+        assert_eq!(parse("c - a - 1", &b).unwrap().value, 7.0);
+        assert_eq!(parse("-a * -b", &b).unwrap().value, -6.0);
+        assert_eq!(parse("2.5e1 * d", &b).unwrap().value, 12.5);
+    }
+
+    #[test]
+    fn test_literals_are_constants() {
+        let parsed = parse("3 * a", &bindings()).unwrap();
+        assert_eq!(parsed.op, Some(Op::Mul('*')));
+        assert!(parsed.prev[0].is_constant());
+        assert_eq!(parsed.prev[1].label, "a");
+        assert!(parse("-4", &bindings()).unwrap().is_constant());
+    }
+
+    #[test]
+    fn test_parse_with_substitutes_graphs() {
+        let ab = Unit::new(2.0f64, "a") * Unit::new(-3.0f64, "b");
+        let lookup = |name: &str| match name {
+            "ab" => Some(ab.clone()),
+            "c" => Some(Unit::new(10.0f64, "c")),
+            _ => None,
+        };
+        let parsed = parse_with("ab + c", &lookup).unwrap();
+        assert_eq!(parsed, ab.clone() + Unit::new(10.0f64, "c"));
+        assert_eq!(parsed.value, 4.0);
+    }
+
+    #[test]
+    fn test_errors_are_positioned() {
+        let b = bindings();
+        assert_eq!(
+            parse("a + x", &b),
+            Err(ParseError {
+                position: 4,
+                kind: ParseErrorKind::UnknownVariable("x".to_string()),
+            })
+        );
+        assert_eq!(
+            parse("relu(a)", &b),
+            Err(ParseError {
+                position: 0,
+                kind: ParseErrorKind::UnknownFunction("relu".to_string()),
+            })
+        );
+        assert_eq!(
+            parse("(a + b", &b),
+            Err(ParseError {
+                position: 6,
+                kind: ParseErrorKind::UnexpectedEnd,
+            })
+        );
+        assert_eq!(
+            parse("a b", &b),
+            Err(ParseError {
+                position: 2,
+                kind: ParseErrorKind::UnexpectedChar('b'),
+            })
+        );
+        assert_eq!(
+            parse("1.2.3", &b),
+            Err(ParseError {
+                position: 0,
+                kind: ParseErrorKind::InvalidNumber("1.2.3".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn test_error_display() {
+        let err = parse("a / b", &bindings()).unwrap_err();
+        assert_eq!(err.to_string(), "unexpected `/` at position 2");
+    }
+}
@@ -0,0 +1,102 @@
+// Weight penalties to add to a loss before `traverse_backward`.
+//
+// A penalty is built over `(label, value)` pairs, as `Model::params` returns
+// them, with one leaf per parameter under its own label, so `leaf_grads` sums
+// its grads into those of the loss. Biases are usually left out, so pass
+// `Model::weights`, as `Trainer` does. For decay applied by the optimizer
+// instead, see the `weight_decay` of `Sgd` and `Adam`.
+use crate::core::Unit;
+
+// `strength` times the sum of absolute values. The grad of each parameter is
+// `strength` times its sign, and zero at zero.
+pub fn l1_penalty(params: &[(&'static str, f64)], strength: f64) -> Unit {
+    let mut total = Unit::constant(0.0);
+    for (label, value) in params.iter() {
+        // |w| as w * sign(w), the sign held constant
+        let sign = match *value {
+            v if v > 0.0 => 1.0,
+            v if v < 0.0 => -1.0,
+            _ => 0.0,
+        };
+        total = Unit::new(*value, label) * Unit::constant(sign) + total;
+    }
+    Unit::constant(strength) * total
+}
+
+// `strength` times the sum of squares. The grad of each parameter is
+// `2 * strength` times its value.
+pub fn l2_penalty(params: &[(&'static str, f64)], strength: f64) -> Unit {
+    let mut total = Unit::constant(0.0);
+    for (label, value) in params.iter() {
+        total = Unit::new(*value, label) * Unit::new(*value, label) + total;
+    }
+    Unit::constant(strength) * total
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Penalty {
+    L1(f64),
+    L2(f64),
+}
+
+impl Penalty {
+    pub fn unit(&self, params: &[(&'static str, f64)]) -> Unit {
+        match *self {
+            Penalty::L1(strength) => l1_penalty(params, strength),
+            Penalty::L2(strength) => l2_penalty(params, strength),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nn::{Activation, Mlp, Model};
+    use alloc::vec::Vec;
+
+    const PARAMS: [(&str, f64); 4] = [("a", 1.5), ("b", -2.0), ("c", 0.0), ("d", 0.25)];
+
+    fn grads(mut penalty: Unit) -> Vec<f64> {
+        penalty.grad = 1.0;
+        penalty.traverse_backward();
+        let grads = penalty.leaf_grads();
+        PARAMS.iter().map(|(label, _)| grads[label]).collect()
+    }
+
+    #[test]
+    fn test_l1_penalty() {
+        let penalty = l1_penalty(&PARAMS, 0.1);
+        assert!((penalty.value - 0.375).abs() < 1e-15);
+        // d/dw 0.1 |w| = 0.1 sign(w)
+        assert_eq!(grads(penalty), [0.1, -0.1, 0.0, 0.1]);
+    }
+
+    #[test]
+    fn test_l2_penalty() {
+        let penalty = Penalty::L2(0.5).unit(&PARAMS);
+        assert_eq!(penalty.value, 0.5 * (2.25 + 4.0 + 0.0 + 0.0625));
+        // d/dw 0.5 w^2 = w
+        let expected: Vec<f64> = PARAMS.iter().map(|(_, w)| w).copied().collect();
+        assert_eq!(grads(penalty), expected);
+        assert_eq!(l2_penalty(&[], 1.0).value, 0.0);
+    }
+
+    #[test]
+    fn test_penalty_adds_to_loss_grads() {
+        let mlp = Mlp::new(&[2, 1], Activation::Linear, 3);
+        let params = mlp.params();
+        let output = mlp.forward(&[1.0, -1.0]).remove(0);
+        let mut plain = output.clone();
+        plain.grad = 1.0;
+        plain.traverse_backward();
+        let plain = plain.leaf_grads();
+
+        let mut loss = output + Penalty::L2(0.25).unit(&params);
+        loss.grad = 1.0;
+        loss.traverse_backward();
+        for (label, grad) in loss.leaf_grads() {
+            let (_, value) = params.iter().find(|(l, _)| *l == label).unwrap();
+            assert!((grad - plain[label] - 0.5 * value).abs() < 1e-12);
+        }
+    }
+}
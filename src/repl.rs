@@ -0,0 +1,256 @@
+use majin::core::Unit;
+use majin::parser::parse_with;
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, Write};
+
+const HELP: &str = "\
+    a = 2.0          define (or redefine) a variable
+    e = a * b + c    define an expression; later lines can use `e`
+    <expr>           print the value of an expression
+    backward(e)      backpropagate from an expression and print leaf grads
+    grad(a)          print the grad of a variable from the last backward
+    show e           open the graph of an expression in the terminal
+    vars             list variables and expressions
+    history          list the lines entered in this session
+    quit             leave the repl";
+
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+    Print(String),
+    Show(Unit),
+    Quit,
+}
+
+#[derive(Default)]
+pub struct Session {
+    variables: BTreeMap<&'static str, f64>,
+    expressions: BTreeMap<&'static str, Unit>,
+    grads: BTreeMap<&'static str, f64>,
+    history: Vec<String>,
+}
+
+impl Session {
+    pub fn execute(&mut self, line: &str) -> Result<Outcome, String> {
+        let line = line.trim();
+        if line.is_empty() {
+            return Ok(Outcome::Print(String::new()));
+        }
+        self.history.push(line.to_owned());
+
+        if let Some(name) = call_argument(line, "backward") {
+            return self.backward(name);
+        }
+        if let Some(name) = call_argument(line, "grad") {
+            return match self.grads.get(name) {
+                Some(grad) => Ok(Outcome::Print(grad.to_string())),
+                None => Err(format!("no grad for `{}`, run backward first", name)),
+            };
+        }
+        if let Some(expr) = line.strip_prefix("show ") {
+            return self.evaluate(expr).map(Outcome::Show);
+        }
+        match line {
+            "quit" | "exit" => return Ok(Outcome::Quit),
+            "help" => return Ok(Outcome::Print(HELP.to_owned())),
+            "vars" => return Ok(Outcome::Print(self.vars())),
+            "history" => return Ok(Outcome::Print(self.history())),
+            _ => {}
+        }
+        if let Some((name, rhs)) = line.split_once('=') {
+            return self.assign(name.trim(), rhs.trim());
+        }
+        self.evaluate(line)
+            .map(|unit| Outcome::Print(unit.value.to_string()))
+    }
+
+    fn assign(&mut self, name: &str, rhs: &str) -> Result<Outcome, String> {
+        if !is_identifier(name) {
+            return Err(format!("`{}` is not a valid name", name));
+        }
+        if let Ok(value) = rhs.parse::<f64>() {
+            let label = self.label(name);
+            self.expressions.remove(label);
+            self.variables.insert(label, value);
+            return Ok(Outcome::Print(format!("{} = {}", label, value)));
+        }
+        let mut unit = self.evaluate(rhs)?;
+        let label = self.label(name);
+        unit.label = label;
+        let value = unit.value;
+        self.variables.remove(label);
+        self.expressions.insert(label, unit);
+        Ok(Outcome::Print(format!("{} = {}", label, value)))
+    }
+
+    fn backward(&mut self, expr: &str) -> Result<Outcome, String> {
+        let mut unit = self.evaluate(expr)?;
+        unit.grad = 1.0;
+        unit.traverse_backward();
+        self.grads = unit.leaf_grads();
+        if let Some(stored) = self.expressions.get_mut(expr.trim()) {
+            *stored = unit;
+        }
+        let lines: Vec<String> = self
+            .grads
+            .iter()
+            .map(|(label, grad)| format!("{}: {}", label, grad))
+            .collect();
+        Ok(Outcome::Print(lines.join("\n")))
+    }
+
+    // Parses `expr` against the session, re-evaluating stored expressions with
+    // the current variable values.
+    fn evaluate(&self, expr: &str) -> Result<Unit, String> {
+        let lookup = |name: &str| {
+            if let Some((label, value)) = self.variables.get_key_value(name) {
+                return Some(Unit::new(*value, label));
+            }
+            self.expressions.get(name).map(|unit| self.refresh(unit))
+        };
+        parse_with(expr, &lookup)
+            .map_err(|err| format!("{}\n    {}\n    {}^", err, expr, " ".repeat(err.position)))
+    }
+
+    fn refresh(&self, unit: &Unit) -> Unit {
+        let mut unit = unit.clone();
+        for (label, value) in self.variables.iter() {
+            unit.set_leaf(label, *value);
+        }
+        unit.forward();
+        unit
+    }
+
+    // Labels are `&'static str`, so each new name is leaked once and reused after.
+    fn label(&self, name: &str) -> &'static str {
+        self.variables
+            .keys()
+            .chain(self.expressions.keys())
+            .find(|label| **label == name)
+            .copied()
+            .unwrap_or_else(|| Box::leak(name.to_owned().into_boxed_str()))
+    }
+
+    fn vars(&self) -> String {
+        let variables = self
+            .variables
+            .iter()
+            .map(|(label, value)| format!("{} = {}", label, value));
+        let expressions = self
+            .expressions
+            .iter()
+            .map(|(label, unit)| format!("{} = {} (expression)", label, self.refresh(unit).value));
+        variables.chain(expressions).collect::<Vec<_>>().join("\n")
+    }
+
+    fn history(&self) -> String {
+        self.history
+            .iter()
+            .enumerate()
+            .map(|(idx, line)| format!("{:>4}  {}", idx + 1, line))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+fn call_argument<'a>(line: &'a str, function: &str) -> Option<&'a str> {
+    line.strip_prefix(function)?
+        .trim_start()
+        .strip_prefix('(')?
+        .strip_suffix(')')
+        .map(str::trim)
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+pub fn run() -> Result<(), String> {
+    let mut session = Session::default();
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    println!("majin repl, type `help` for commands");
+    loop {
+        print!("majin> ");
+        io::stdout().flush().map_err(|err| err.to_string())?;
+        let line = match lines.next() {
+            Some(line) => line.map_err(|err| err.to_string())?,
+            None => return Ok(()),
+        };
+        match session.execute(&line) {
+            Ok(Outcome::Print(text)) if text.is_empty() => {}
+            Ok(Outcome::Print(text)) => println!("{}", text),
+            Ok(Outcome::Show(unit)) => {
+                if let Err(message) = crate::show(&unit) {
+                    eprintln!("error: {}", message);
+                }
+            }
+            Ok(Outcome::Quit) => return Ok(()),
+            Err(message) => eprintln!("error: {}", message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn print(session: &mut Session, line: &str) -> String {
+        match session.execute(line) {
+            Ok(Outcome::Print(text)) => text,
+            other => panic!("unexpected outcome {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_variables_and_expressions() {
+        let mut session = Session::default();
+        assert_eq!(print(&mut session, "a = 2.0"), "a = 2");
+        assert_eq!(print(&mut session, "b = -3"), "b = -3");
+        assert_eq!(print(&mut session, "ab = a * b"), "ab = -6");
+        assert_eq!(print(&mut session, "c = 10"), "c = 10");
+        assert_eq!(print(&mut session, "e = ab + c"), "e = 4");
+        assert_eq!(print(&mut session, "e * 2"), "8");
+        // redefining a variable re-evaluates the expressions built from it
+        print(&mut session, "a = 1");
+        assert_eq!(print(&mut session, "e"), "7");
+    }
+
+    #[test]
+    fn test_backward_and_grad() {
+        let mut session = Session::default();
+        print(&mut session, "a = 2");
+        print(&mut session, "b = -3");
+        print(&mut session, "e = a * b + a");
+        assert_eq!(print(&mut session, "backward(e)"), "a: -2\nb: 2");
+        assert_eq!(print(&mut session, "grad(a)"), "-2");
+        assert_eq!(print(&mut session, "grad( b )"), "2");
+        assert!(session.execute("grad(c)").is_err());
+    }
+
+    #[test]
+    fn test_show_and_quit() {
+        let mut session = Session::default();
+        print(&mut session, "a = 2");
+        match session.execute("show a * 3") {
+            Ok(Outcome::Show(unit)) => assert_eq!(unit.value, 6.0),
+            other => panic!("unexpected outcome {:?}", other),
+        }
+        assert_eq!(session.execute("quit"), Ok(Outcome::Quit));
+    }
+
+    #[test]
+    fn test_errors_and_history() {
+        let mut session = Session::default();
+        assert!(session.execute("a + 1").is_err());
+        assert!(session.execute("1a = 2").is_err());
+        print(&mut session, "a = 1");
+        print(&mut session, "");
+        assert_eq!(
+            print(&mut session, "history"),
+            "   1  a + 1\n   2  1a = 2\n   3  a = 1\n   4  history"
+        );
+        assert_eq!(print(&mut session, "vars"), "a = 1");
+    }
+}
@@ -0,0 +1,320 @@
+// Learning-rate schedules.
+//
+// A scheduler holds the rate for the current step and moves on with `step`,
+// once per epoch or per batch as the caller decides; `apply` copies the rate
+// into any optimizer. `step` takes the value being monitored, a loss or a
+// metric, which only `ReduceOnPlateau` looks at. Powers go through `libm` so
+// the rates are the same on every target.
+use crate::optim::Optimizer;
+use core::f64::consts::PI;
+use libm::{cos, pow};
+
+// How often `Trainer` steps a scheduler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interval {
+    Batch,
+    Epoch,
+}
+
+// Which way the monitored value improves, for `ReduceOnPlateau` and the
+// trainer's early stopping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Goal {
+    Minimize,
+    Maximize,
+}
+
+pub trait Scheduler {
+    fn learning_rate(&self) -> f64;
+
+    fn step(&mut self, monitored: f64);
+
+    fn apply(&self, optimizer: &mut dyn Optimizer) {
+        optimizer.set_learning_rate(self.learning_rate());
+    }
+}
+
+// The same rate throughout, e.g. after a `LinearWarmup`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Constant(pub f64);
+
+impl Scheduler for Constant {
+    fn learning_rate(&self) -> f64 {
+        self.0
+    }
+
+    fn step(&mut self, _monitored: f64) {}
+}
+
+// Multiplies the rate by `gamma` every `step_size` steps.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepDecay {
+    pub base: f64,
+    pub step_size: usize,
+    pub gamma: f64,
+    steps: usize,
+}
+
+impl StepDecay {
+    pub fn new(base: f64, step_size: usize, gamma: f64) -> Self {
+        StepDecay {
+            base,
+            step_size: step_size.max(1),
+            gamma,
+            steps: 0,
+        }
+    }
+}
+
+impl Scheduler for StepDecay {
+    fn learning_rate(&self) -> f64 {
+        self.base * pow(self.gamma, (self.steps / self.step_size) as f64)
+    }
+
+    fn step(&mut self, _monitored: f64) {
+        self.steps += 1;
+    }
+}
+
+// Multiplies the rate by `gamma` every step.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Exponential {
+    pub base: f64,
+    pub gamma: f64,
+    steps: usize,
+}
+
+impl Exponential {
+    pub fn new(base: f64, gamma: f64) -> Self {
+        Exponential {
+            base,
+            gamma,
+            steps: 0,
+        }
+    }
+}
+
+impl Scheduler for Exponential {
+    fn learning_rate(&self) -> f64 {
+        self.base * pow(self.gamma, self.steps as f64)
+    }
+
+    fn step(&mut self, _monitored: f64) {
+        self.steps += 1;
+    }
+}
+
+// Half a cosine from `base` down to `min` over `period` steps, then `min`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CosineAnnealing {
+    pub base: f64,
+    pub min: f64,
+    pub period: usize,
+    steps: usize,
+}
+
+impl CosineAnnealing {
+    pub fn new(base: f64, min: f64, period: usize) -> Self {
+        CosineAnnealing {
+            base,
+            min,
+            period: period.max(1),
+            steps: 0,
+        }
+    }
+}
+
+impl Scheduler for CosineAnnealing {
+    fn learning_rate(&self) -> f64 {
+        let progress = self.steps.min(self.period) as f64 / self.period as f64;
+        self.min + (self.base - self.min) * (1.0 + cos(PI * progress)) / 2.0
+    }
+
+    fn step(&mut self, _monitored: f64) {
+        self.steps += 1;
+    }
+}
+
+// Ramps up linearly to the rate of `after` over `steps` steps, starting at
+// 1 / `steps` of it, then hands over to `after`, which only starts stepping
+// once the warmup is done.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinearWarmup<S> {
+    pub steps: usize,
+    pub after: S,
+    taken: usize,
+}
+
+impl<S: Scheduler> LinearWarmup<S> {
+    pub fn new(steps: usize, after: S) -> Self {
+        LinearWarmup {
+            steps,
+            after,
+            taken: 0,
+        }
+    }
+}
+
+impl<S: Scheduler> Scheduler for LinearWarmup<S> {
+    fn learning_rate(&self) -> f64 {
+        match self.taken < self.steps {
+            true => self.after.learning_rate() * (self.taken + 1) as f64 / self.steps as f64,
+            false => self.after.learning_rate(),
+        }
+    }
+
+    fn step(&mut self, monitored: f64) {
+        match self.taken < self.steps {
+            true => self.taken += 1,
+            false => self.after.step(monitored),
+        }
+    }
+}
+
+// Multiplies the rate by `factor`, down to `min`, whenever the monitored value
+// has gone more than `patience` steps without improving on the best so far by
+// more than `threshold`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReduceOnPlateau {
+    pub factor: f64,
+    pub patience: usize,
+    pub threshold: f64,
+    pub min: f64,
+    pub goal: Goal,
+    rate: f64,
+    best: Option<f64>,
+    waited: usize,
+}
+
+impl ReduceOnPlateau {
+    pub fn new(base: f64, factor: f64, patience: usize, goal: Goal) -> Self {
+        ReduceOnPlateau {
+            factor,
+            patience,
+            threshold: 0.0,
+            min: 0.0,
+            goal,
+            rate: base,
+            best: None,
+            waited: 0,
+        }
+    }
+}
+
+impl Scheduler for ReduceOnPlateau {
+    fn learning_rate(&self) -> f64 {
+        self.rate
+    }
+
+    fn step(&mut self, monitored: f64) {
+        let improved = match (self.best, self.goal) {
+            (None, _) => true,
+            (Some(best), Goal::Minimize) => monitored < best - self.threshold,
+            (Some(best), Goal::Maximize) => monitored > best + self.threshold,
+        };
+        if improved {
+            self.best = Some(monitored);
+            self.waited = 0;
+            return;
+        }
+        self.waited += 1;
+        if self.waited > self.patience {
+            self.rate = (self.rate * self.factor).max(self.min);
+            self.waited = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::optim::Sgd;
+    use alloc::vec::Vec;
+
+    // The rate at every step, starting before the first.
+    fn rates(scheduler: &mut dyn Scheduler, monitored: &[f64]) -> Vec<f64> {
+        let mut rates = Vec::from([scheduler.learning_rate()]);
+        for value in monitored {
+            scheduler.step(*value);
+            rates.push(scheduler.learning_rate());
+        }
+        rates
+    }
+
+    #[test]
+    fn test_step_decay() {
+        let mut scheduler = StepDecay::new(1.0, 2, 0.5);
+        assert_eq!(
+            rates(&mut scheduler, &[0.0; 6]),
+            [1.0, 1.0, 0.5, 0.5, 0.25, 0.25, 0.125]
+        );
+    }
+
+    #[test]
+    fn test_exponential() {
+        let mut scheduler = Exponential::new(0.8, 0.5);
+        assert_eq!(rates(&mut scheduler, &[0.0; 4]), [0.8, 0.4, 0.2, 0.1, 0.05]);
+    }
+
+    #[test]
+    fn test_cosine_annealing() {
+        let mut scheduler = CosineAnnealing::new(1.0, 0.2, 4);
+        let rates = rates(&mut scheduler, &[0.0; 6]);
+        let expected = [
+            1.0,
+            0.2 + 0.8 * (1.0 + cos(PI / 4.0)) / 2.0,
+            0.6,
+            0.2 + 0.8 * (1.0 + cos(3.0 * PI / 4.0)) / 2.0,
+            0.2,
+            0.2,
+            0.2,
+        ];
+        assert_eq!(rates.len(), expected.len());
+        for (rate, expected) in rates.iter().zip(expected) {
+            assert!((rate - expected).abs() < 1e-15, "{} != {}", rate, expected);
+        }
+    }
+
+    #[test]
+    fn test_linear_warmup() {
+        let mut scheduler = LinearWarmup::new(4, Constant(1.0));
+        assert_eq!(
+            rates(&mut scheduler, &[0.0; 5]),
+            [0.25, 0.5, 0.75, 1.0, 1.0, 1.0]
+        );
+        // the decay starts counting once the warmup is over
+        let mut scheduler = LinearWarmup::new(2, Exponential::new(1.0, 0.5));
+        assert_eq!(rates(&mut scheduler, &[0.0; 4]), [0.5, 1.0, 1.0, 0.5, 0.25]);
+        let mut scheduler = LinearWarmup::new(0, Constant(0.3));
+        assert_eq!(rates(&mut scheduler, &[0.0; 2]), [0.3, 0.3, 0.3]);
+    }
+
+    #[test]
+    fn test_reduce_on_plateau() {
+        let mut scheduler = ReduceOnPlateau::new(1.0, 0.5, 1, Goal::Minimize);
+        scheduler.min = 0.3;
+        let losses = [1.0, 0.9, 0.95, 0.92, 0.8, 0.85, 0.9, 0.9, 0.9, 0.9];
+        assert_eq!(
+            rates(&mut scheduler, &losses),
+            [1.0, 1.0, 1.0, 1.0, 0.5, 0.5, 0.5, 0.3, 0.3, 0.3, 0.3]
+        );
+
+        let mut scheduler = ReduceOnPlateau::new(1.0, 0.1, 0, Goal::Maximize);
+        scheduler.threshold = 0.05;
+        assert_eq!(
+            rates(&mut scheduler, &[0.5, 0.6, 0.62]),
+            [1.0, 1.0, 1.0, 0.1]
+        );
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut sgd = Sgd::new(1.0);
+        let mut scheduler = Exponential::new(0.5, 0.5);
+        scheduler.apply(&mut sgd);
+        assert_eq!(sgd.learning_rate, 0.5);
+        scheduler.step(0.0);
+        scheduler.apply(&mut sgd);
+        assert_eq!(sgd.learning_rate(), 0.25);
+    }
+}
@@ -0,0 +1,203 @@
+use crate::core::{Op, Unit};
+
+impl Unit {
+    // Builds a new graph for d(self)/d(wrt), where `wrt` is the label of a leaf.
+    // Each op is differentiated with the same rule `Unit::backward` applies, so
+    // evaluating the result gives the same number as the numeric grad of `wrt`
+    // (summed over every leaf carrying that label).
+    pub fn derivative_graph(&self, wrt: &str) -> Unit {
+        derive(self, wrt)
+            .unwrap_or_else(|| Unit::constant(0.0))
+            .simplify()
+    }
+
+    // Folds constant subgraphs and drops additions of 0 and multiplications by 1.
+    pub fn simplify(&self) -> Unit {
+        match self.op {
+            Some(Op::Add(_)) => {
+                let lhs = self.prev[0].simplify();
+                let rhs = self.prev[1].simplify();
+                match (lhs.is_constant(), rhs.is_constant()) {
+                    (true, true) => Unit::constant(lhs.value + rhs.value),
+                    (true, false) if lhs.value == 0.0 => rhs,
+                    (false, true) if rhs.value == 0.0 => lhs,
+                    _ => relabel(lhs + rhs, self.label),
+                }
+            }
+            Some(Op::Mul(_)) => {
+                let lhs = self.prev[0].simplify();
+                let rhs = self.prev[1].simplify();
+                match (lhs.is_constant(), rhs.is_constant()) {
+                    (true, true) => Unit::constant(lhs.value * rhs.value),
+                    // only a constant zero; a leaf holding 0 can change
+                    (true, false) if lhs.value == 0.0 => Unit::constant(0.0),
+                    (false, true) if rhs.value == 0.0 => Unit::constant(0.0),
+                    (true, false) if lhs.value == 1.0 => rhs,
+                    (false, true) if rhs.value == 1.0 => lhs,
+                    _ => relabel(lhs * rhs, self.label),
+                }
+            }
+            Some(Op::Tanh(_)) => {
+                let input = self.prev[0].simplify();
+                if input.is_constant() {
+                    Unit::constant(libm::tanh(input.value))
+                } else {
+                    relabel(input.tanh(), self.label)
+                }
+            }
+            _ => self.clone(),
+        }
+    }
+}
+
+fn relabel(mut unit: Unit, label: &'static str) -> Unit {
+    unit.label = label;
+    unit
+}
+
+// `None` stands for a derivative that is identically zero.
+fn derive(node: &Unit, wrt: &str) -> Option<Unit> {
+    match node.op {
+        None => {
+            if !node.is_constant() && node.label == wrt {
+                Some(Unit::constant(1.0))
+            } else {
+                None
+            }
+        }
+        Some(Op::Add(_)) => {
+            // f(x) = x + y => df = dx + dy
+            match (derive(&node.prev[0], wrt), derive(&node.prev[1], wrt)) {
+                (Some(dx), Some(dy)) => Some(dx + dy),
+                (Some(d), None) | (None, Some(d)) => Some(d),
+                (None, None) => None,
+            }
+        }
+        Some(Op::Mul(_)) => {
+            // f(x) = x * y => df = y * dx + x * dy
+            let x = node.prev[0].as_ref();
+            let y = node.prev[1].as_ref();
+            let lhs = derive(x, wrt).map(|dx| y.clone() * dx);
+            let rhs = derive(y, wrt).map(|dy| x.clone() * dy);
+            match (lhs, rhs) {
+                (Some(lhs), Some(rhs)) => Some(lhs + rhs),
+                (Some(d), None) | (None, Some(d)) => Some(d),
+                (None, None) => None,
+            }
+        }
+        Some(Op::Tanh(_)) => {
+            // f(x) = tanh(x) => df = (1 - tanh^2(x)) * dx, and node is tanh(x)
+            let dx = derive(&node.prev[0], wrt)?;
+            let t = node.clone();
+            let local = Unit::constant(1.0) + Unit::constant(-1.0) * (t.clone() * t);
+            Some(local * dx)
+        }
+        // Sigmoid and Relu have no backward rule yet, so they pass no gradient.
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numeric_grad(root: &Unit, label: &str) -> f64 {
+        fn sum(node: &Unit, label: &str) -> f64 {
+            if node.prev.is_empty() {
+                if node.label == label {
+                    node.grad
+                } else {
+                    0.0
+                }
+            } else {
+                node.prev.iter().map(|child| sum(child, label)).sum()
+            }
+        }
+        let mut root = root.clone();
+        root.grad = 1.0;
+        root.traverse_backward();
+        sum(&root, label)
+    }
+
+    #[test]
+    fn test_derivative_of_product() {
+        let a = Unit::new(2.0f64, "a");
+        let b = Unit::new(-3.0f64, "b");
+        let c = Unit::new(10.0f64, "c");
+        let root = a * b + c;
+        let da = root.derivative_graph("a");
+        assert_eq!(da.value, -3.0);
+        // d/da (a * b + c) = b, so the graph collapses to the `b` leaf
+        assert_eq!(da, Unit::new(-3.0f64, "b"));
+        let dc = root.derivative_graph("c");
+        assert!(dc.is_constant());
+        assert_eq!(dc.value, 1.0);
+    }
+
+    #[test]
+    fn test_derivative_of_unrelated_leaf_is_zero() {
+        let root = Unit::new(2.0f64, "a") * Unit::new(3.0f64, "b");
+        let dz = root.derivative_graph("z");
+        assert!(dz.is_constant());
+        assert_eq!(dz.value, 0.0);
+    }
+
+    #[test]
+    fn test_derivative_matches_backward() {
+        let tolerance = 1e-12;
+        let a = Unit::new(0.50f64, "a");
+        let b = Unit::new(0.75f64, "b");
+        let c = Unit::new(0.25f64, "c");
+        let d = Unit::new(0.10f64, "d");
+        let root = ((a.clone() * b + c + d) * a).tanh();
+        for label in ["a", "b", "c", "d"] {
+            let derivative = root.derivative_graph(label);
+            assert!((derivative.value - numeric_grad(&root, label)).abs() < tolerance);
+        }
+    }
+
+    #[test]
+    fn test_derivative_at_other_points() {
+        let a = Unit::new(2.0f64, "a");
+        let b = Unit::new(5.0f64, "b");
+        // d/da (a * a * b) = 2ab
+        let root = a.clone() * a * b;
+        let mut da = root.derivative_graph("a");
+        assert_eq!(da.value, 20.0);
+        da.set_leaf("a", 3.0);
+        da.set_leaf("b", -1.0);
+        da.forward();
+        assert_eq!(da.value, -6.0);
+    }
+
+    #[test]
+    fn test_simplify_folds_constants() {
+        let x = Unit::new(4.0f64, "x");
+        let expr = (Unit::constant(2.0) * Unit::constant(0.5)) * x.clone() + Unit::constant(0.0);
+        assert_eq!(expr.simplify(), x);
+        let folded = (Unit::constant(0.0) * x + Unit::constant(3.0)).simplify();
+        assert!(folded.is_constant());
+        assert_eq!(folded.value, 3.0);
+    }
+
+    #[test]
+    fn test_simplify_keeps_leaves_holding_zero() {
+        // d/dx (x * x) built at x = 0 must not fold to the constant 0
+        let x = Unit::new(0.0f64, "x");
+        let mut dx = (x.clone() * x).derivative_graph("x");
+        assert_eq!(dx.value, 0.0);
+        assert!(!dx.is_constant());
+        dx.set_leaf("x", 3.0);
+        dx.forward();
+        assert_eq!(dx.value, 6.0);
+    }
+
+    #[test]
+    fn test_derivative_of_tanh() {
+        let x = Unit::new(0.5f64, "x");
+        let dx = x.tanh().derivative_graph("x");
+        let t = libm::tanh(0.5);
+        assert!((dx.value - (1.0 - t * t)).abs() < 1e-15);
+        assert!((dx.value - 0.786448).abs() < 1e-6);
+    }
+}
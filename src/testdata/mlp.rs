@@ -0,0 +1,32 @@
+// Generated by majin. Do not edit.
+#[allow(clippy::all, unused_parens)]
+pub fn mlp_forward(inputs: &[f64]) -> [f64; 2] {
+    let v0 = (-0.19738705477890284f64) * inputs[1];
+    let v1 = (-0.44584246623425705f64) * inputs[0];
+    let v2 = v1 + 0.0f64;
+    let v3 = v0 + v2;
+    let v4 = libm::tanh(v3);
+    let v5 = (-0.4755289700194003f64) * inputs[1];
+    let v6 = (-0.774487764316071f64) * inputs[0];
+    let v7 = v6 + 0.0f64;
+    let v8 = v5 + v7;
+    let v9 = libm::tanh(v8);
+    let v10 = 0.12021515926213389f64 * inputs[1];
+    let v11 = 1.0369310184379148f64 * inputs[0];
+    let v12 = v11 + 0.0f64;
+    let v13 = v10 + v12;
+    let v14 = libm::tanh(v13);
+    let v15 = 0.35765043722325984f64 * v14;
+    let v16 = (-0.21909008305385091f64) * v9;
+    let v17 = (-0.8085143587585208f64) * v4;
+    let v18 = v17 + 0.0f64;
+    let v19 = v16 + v18;
+    let v20 = v15 + v19;
+    let v21 = (-0.8008370375097347f64) * v14;
+    let v22 = 0.707703852019812f64 * v9;
+    let v23 = 0.546991770622997f64 * v4;
+    let v24 = v23 + 0.0f64;
+    let v25 = v22 + v24;
+    let v26 = v21 + v25;
+    [v20, v26]
+}
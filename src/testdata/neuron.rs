@@ -0,0 +1,14 @@
+// Generated by majin. Do not edit.
+#[allow(clippy::all, unused_parens)]
+pub fn neuron_forward(x1: f64, x2: f64) -> f64 {
+    let v0 = x1 * (-3.0f64);
+    let v1 = x2 * 1.0f64;
+    let v2 = v0 + v1;
+    let v3 = v2 + 6.881373587019543f64;
+    let v4 = libm::tanh(v3);
+    let v5 = x1 * 0.5f64;
+    let v6 = v4 + v5;
+    let v7 = libm::tanh(v6);
+    let v8 = v7 * v4;
+    v8
+}
@@ -0,0 +1,188 @@
+// Flattens a `Unit` tree into its distinct nodes and the edges between them,
+// for drawing. Equal subtrees, which the tree representation duplicates, are
+// listed once.
+use crate::core::Unit;
+use alloc::vec::Vec;
+
+// (node, parent it was first reached from, depth below the root)
+pub type TracedNode<'a> = (&'a Unit, Option<&'a Unit>, usize);
+// (input, node it feeds into)
+pub type TracedEdge<'a> = (&'a Unit, &'a Unit);
+
+pub fn trace(root: &Unit) -> (Vec<TracedNode<'_>>, Vec<TracedEdge<'_>>) {
+    let mut nodes = Vec::new();
+    let mut edges = Vec::new();
+
+    fn build<'a>(
+        v: &'a Unit,
+        parent: Option<&'a Unit>,
+        nodes: &mut Vec<TracedNode<'a>>,
+        edges: &mut Vec<TracedEdge<'a>>,
+        level: usize,
+    ) {
+        // Check if the current node `v` is already in `nodes_with_levels`:
+        // - `nodes_with_levels.iter()`: Creates an iterator over the `nodes_with_levels` vector.
+        //   Each item in the iterator is a reference to a tuple `(&Unit, usize)`.
+        // - `.any(|(node, _)| *node == v)`: The `.any()` method checks if any item in the iterator
+        //   satisfies the provided condition. The closure `|(node, _)| *node == v` is the condition.
+        //   This closure takes each tuple `(node, level)` (where `level` is ignored with `_`) and checks
+        //   if `node` (which is a reference to a `Unit`) matches the node `v`.
+        // - `*node`: Dereferences the `&Unit` reference, so you can directly compare it to `v`.
+        // - `!`: Negates the result. If `.any()` returns `true` (meaning the node is already in the vector),
+        //   the `!` turns it into `false`, indicating that the node should not be added again.
+        let node_exists = nodes.iter().any(|(node, _, _)| *node == v);
+        if !node_exists {
+            nodes.push((v, parent, level));
+            for prev in &v.prev {
+                edges.push((prev.as_ref(), v));
+                build(prev.as_ref(), Some(v), nodes, edges, level + 1);
+            }
+        }
+    }
+
+    build(root, None, &mut nodes, &mut edges, 0);
+    (nodes, edges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trace_single_node() {
+        let root = Unit::new(0.0f64, "root");
+        let (nodes, edges) = trace(&root);
+
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(nodes[0].0, &root);
+        assert!(edges.is_empty());
+    }
+
+    #[test]
+    fn test_trace_single_node_f32() {
+        let root = Unit::new(0.0f64, "root");
+        let (nodes, edges) = trace(&root);
+
+        assert_eq!(nodes.len(), 1);
+        // (&root, parent, level)
+        // first element in the vector is the root node
+        // the parent is None because it is the root node
+        // the level is 0 because it is the root node
+        // first element in the tuple is a reference to the root node
+        assert_eq!(nodes[0].0, &root);
+        assert!(edges.is_empty());
+    }
+
+    #[test]
+    fn test_trace_multiple_nodes_f32() {
+        let leaf1 = Unit::new(2.0f64, "leaf1");
+        let leaf2 = Unit::new(3.0f64, "leaf2");
+        let root = leaf1.clone() + leaf2.clone();
+
+        let (nodes, edges) = trace(&root);
+
+        assert_eq!(nodes.len(), 3);
+        // search for the reference to the root node
+        assert!(nodes.iter().any(|(node, _, _)| *node == &root));
+        // search for the reference to the leaf1 node
+        assert!(nodes.iter().any(|(node, _, _)| *node == &leaf1));
+        // search for the reference to the leaf2 node
+        assert!(nodes.iter().any(|(node, _, _)| *node == &leaf2));
+
+        assert_eq!(edges.len(), 2);
+        assert!(edges.iter().any(|(n1, n2)| *n1 == &leaf1 && *n2 == &root));
+        assert!(edges.iter().any(|(n1, n2)| *n1 == &leaf2 && *n2 == &root));
+    }
+
+    #[test]
+    fn test_trace_multiple_nodes() {
+        let leaf1 = Unit::new(2.0f64, "leaf1");
+        let leaf2 = Unit::new(3.0f64, "leaf2");
+        let root = leaf1.clone() + leaf2.clone();
+
+        let (nodes, edges) = trace(&root);
+
+        assert_eq!(nodes.len(), 3);
+        assert!(nodes.iter().any(|(node, _, _)| *node == &root));
+        assert!(nodes.iter().any(|(node, _, _)| *node == &leaf1));
+        assert!(nodes.iter().any(|(node, _, _)| *node == &leaf2));
+
+        assert_eq!(edges.len(), 2);
+        assert!(edges.iter().any(|(n1, n2)| *n1 == &leaf1 && *n2 == &root));
+        assert!(edges.iter().any(|(n1, n2)| *n1 == &leaf2 && *n2 == &root));
+    }
+
+    #[test]
+    fn test_trace_deep_tree() {
+        let leaf1 = Unit::new(2.0f64, "leaf1");
+        let leaf2 = Unit::new(3.0f64, "leaf2");
+        let leaf3 = Unit::new(4.0f64, "leaf3");
+        let leaf4 = Unit::new(5.0f64, "leaf4");
+        // 25 = (2 + 3) * 4 + 5
+        let root = (leaf1.clone() + leaf2.clone()) * leaf3.clone() + leaf4.clone();
+
+        let (nodes, edges) = trace(&root);
+
+        assert_eq!(nodes.len(), 7);
+
+        // Validate the root node and its connections
+        assert!(nodes.iter().any(|(node, _, _)| *node == &root)); // root 25
+        assert_eq!(root.value, 25.0);
+
+        // Validate connections and operations
+        assert!(nodes.iter().any(|(node, _, _)| **node == *root.prev[0])); // 20 (result of 5 * 4)
+        assert_eq!(root.prev[0].value, 20.0);
+
+        assert!(nodes.iter().any(|(node, _, _)| **node == *root.prev[1])); // 5
+        assert_eq!(root.prev[1].value, 5.0);
+
+        assert!(nodes
+            .iter()
+            .any(|(node, _, _)| **node == *root.prev[0].prev[0])); // 5 (result of 2 + 3)
+        assert_eq!(root.prev[0].prev[0].value, 5.0);
+
+        assert!(nodes
+            .iter()
+            .any(|(node, _, _)| **node == *root.prev[0].prev[1])); // 4
+        assert_eq!(root.prev[0].prev[1].value, 4.0);
+
+        assert!(nodes
+            .iter()
+            .any(|(node, _, _)| **node == *root.prev[0].prev[0].prev[0])); // 2
+        assert_eq!(root.prev[0].prev[0].prev[0].value, 2.0);
+
+        assert!(nodes
+            .iter()
+            .any(|(node, _, _)| **node == *root.prev[0].prev[0].prev[1])); // 3
+        assert_eq!(root.prev[0].prev[0].prev[1].value, 3.0);
+
+        // Validate the edges
+        assert_eq!(edges.len(), 6);
+        assert!(edges
+            .iter()
+            .any(|(n1, n2)| **n1 == *root.prev[0] && **n2 == root)); // 20 -> root
+        assert!(edges
+            .iter()
+            .any(|(n1, n2)| **n1 == *root.prev[1] && **n2 == root)); // 5 -> root
+
+        assert!(edges
+            .iter()
+            .any(|(n1, n2)| **n1 == *root.prev[0].prev[0] && **n2 == *root.prev[0])); // 5 -> 20
+        assert!(edges
+            .iter()
+            .any(|(n1, n2)| **n1 == *root.prev[0].prev[1] && **n2 == *root.prev[0])); // 4 -> 20
+
+        assert!(
+            edges
+                .iter()
+                .any(|(n1, n2)| **n1 == *root.prev[0].prev[0].prev[0]
+                    && **n2 == *root.prev[0].prev[0])
+        ); // 2 -> 5
+        assert!(
+            edges
+                .iter()
+                .any(|(n1, n2)| **n1 == *root.prev[0].prev[0].prev[1]
+                    && **n2 == *root.prev[0].prev[0])
+        ); // 3 -> 5
+    }
+}
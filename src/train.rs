@@ -0,0 +1,208 @@
+use majin::data::{InMemory, Sample};
+use majin::nn::{squared_error, Activation, Mlp};
+use majin::optim::{Clip, Sgd};
+use majin::schedule::{CosineAnnealing, Goal, Interval};
+use majin::trainer::{Control, EarlyStopping, Trainer};
+use std::cell::RefCell;
+
+pub struct Options {
+    pub epochs: usize,
+    pub learning_rate: f64,
+    pub seed: u64,
+    // `None` trains on all samples at once
+    pub batch_size: Option<usize>,
+    // largest grad norm per step
+    pub clip: Option<f64>,
+    // epochs without a lower loss before stopping early
+    pub patience: Option<usize>,
+    // anneal the learning rate to 0 over the epochs
+    pub cosine: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            epochs: 300,
+            learning_rate: 0.2,
+            seed: 1,
+            batch_size: None,
+            clip: None,
+            patience: None,
+            cosine: false,
+        }
+    }
+}
+
+// What every epoch reports to a progress printer or the dashboard.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EpochStats {
+    pub epoch: usize,
+    pub loss: f64,
+    pub accuracy: f64,
+    pub learning_rate: f64,
+    // of the epoch's last batch, after any clipping
+    pub grad_norms: Vec<f64>,
+}
+
+pub fn model(samples: &[Sample], seed: u64) -> Mlp {
+    let inputs = samples.first().map_or(0, |(x, _)| x.len());
+    let outputs = samples.first().map_or(0, |(_, y)| y.len());
+    Mlp::new(&[inputs, 4, outputs], Activation::Tanh, seed)
+}
+
+// Gradient descent on the squared error with a `Trainer`, calling `on_epoch`
+// after every epoch. Training stops early when `on_epoch` returns false.
+pub fn train(
+    mlp: &mut Mlp,
+    samples: &[Sample],
+    options: &Options,
+    on_epoch: &mut dyn FnMut(&Mlp, &EpochStats) -> bool,
+) {
+    let data = InMemory::new(samples.to_vec());
+    let grad_norms = RefCell::new(Vec::new());
+    let mut trainer = Trainer::new(mlp.clone(), Sgd::new(options.learning_rate), squared_error)
+        .epochs(options.epochs)
+        .batch_size(options.batch_size.unwrap_or(samples.len()).max(1))
+        .seed(options.seed)
+        .on_batch_end(|mlp: &mut Mlp, _, stats| {
+            *grad_norms.borrow_mut() = mlp.layer_grad_norms(&stats.grads);
+            Control::Continue
+        })
+        .on_epoch_end(|mlp: &mut Mlp, _, stats| {
+            let stats = EpochStats {
+                epoch: stats.epoch,
+                loss: stats.loss,
+                accuracy: accuracy(mlp, samples),
+                learning_rate: stats.learning_rate,
+                grad_norms: grad_norms.borrow().clone(),
+            };
+            match on_epoch(mlp, &stats) {
+                true => Control::Continue,
+                false => Control::Stop,
+            }
+        });
+    if let Some(max_norm) = options.clip {
+        trainer = trainer.clip(Clip::Norm(max_norm));
+    }
+    if let Some(patience) = options.patience {
+        trainer = trainer.early_stopping(EarlyStopping::new(patience, Goal::Minimize));
+    }
+    if options.cosine {
+        let schedule = CosineAnnealing::new(options.learning_rate, 0.0, options.epochs);
+        trainer = trainer.scheduler(schedule, Interval::Epoch);
+    }
+    trainer.fit(&data);
+    *mlp = trainer.model;
+}
+
+// Share of samples whose outputs all have the sign of their targets.
+pub fn accuracy(mlp: &Mlp, samples: &[Sample]) -> f64 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    let correct = samples
+        .iter()
+        .filter(|(inputs, targets)| {
+            mlp.predict(inputs)
+                .iter()
+                .zip(targets.iter())
+                .all(|(output, target)| (*output > 0.0) == (*target > 0.0))
+        })
+        .count();
+    correct as f64 / samples.len() as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use majin::generate::xor_corners;
+
+    #[test]
+    fn test_train_reports_every_epoch() {
+        let samples = xor_corners().into_samples();
+        let mut mlp = model(&samples, 1);
+        let options = Options {
+            epochs: 5,
+            ..Options::default()
+        };
+        let mut seen = Vec::new();
+        train(&mut mlp, &samples, &options, &mut |_, stats| {
+            seen.push(stats.clone());
+            true
+        });
+        assert_eq!(seen.len(), 5);
+        assert_eq!(seen[4].epoch, 5);
+        assert_eq!(seen[0].grad_norms.len(), 2);
+        assert!(seen[4].loss < seen[0].loss);
+    }
+
+    #[test]
+    fn test_train_stops_when_asked() {
+        let samples = xor_corners().into_samples();
+        let mut mlp = model(&samples, 1);
+        let mut epochs = 0;
+        train(&mut mlp, &samples, &Options::default(), &mut |_, stats| {
+            epochs = stats.epoch;
+            stats.epoch < 3
+        });
+        assert_eq!(epochs, 3);
+    }
+
+    #[test]
+    fn test_train_options() {
+        let samples = xor_corners().into_samples();
+        let options = Options {
+            epochs: 4,
+            batch_size: Some(2),
+            clip: Some(0.01),
+            cosine: true,
+            ..Options::default()
+        };
+        let mut seen = Vec::new();
+        train(
+            &mut model(&samples, 1),
+            &samples,
+            &options,
+            &mut |_, stats| {
+                seen.push(stats.clone());
+                true
+            },
+        );
+        assert_eq!(seen.len(), 4);
+        // the cosine schedule starts from the base rate and goes down
+        assert_eq!(seen[0].learning_rate, 0.2);
+        assert!(seen[3].learning_rate < seen[2].learning_rate);
+        let norm = libm::sqrt(seen[0].grad_norms.iter().map(|n| n * n).sum::<f64>());
+        assert!(norm <= 0.01 + 1e-12);
+    }
+
+    #[test]
+    fn test_train_stops_early() {
+        let samples = xor_corners().into_samples();
+        // a rate this high only makes the loss worse
+        let options = Options {
+            learning_rate: 100.0,
+            patience: Some(2),
+            ..Options::default()
+        };
+        let mut epochs = 0;
+        train(
+            &mut model(&samples, 1),
+            &samples,
+            &options,
+            &mut |_, stats| {
+                epochs = stats.epoch;
+                true
+            },
+        );
+        assert!(epochs < options.epochs);
+    }
+
+    #[test]
+    fn test_learns_xor() {
+        let samples = xor_corners().into_samples();
+        let mut mlp = model(&samples, 1);
+        train(&mut mlp, &samples, &Options::default(), &mut |_, _| true);
+        assert_eq!(accuracy(&mlp, &samples), 1.0);
+    }
+}
@@ -0,0 +1,534 @@
+// Mini-batch training loop: forward, loss, backward and optimizer step.
+//
+// Every epoch walks the training set in a fresh shuffled order, averages the
+// per-sample loss over each batch and steps the optimizer once per batch. Grads
+// start from scratch with every batch, since each forward pass builds a new
+// graph. Callbacks run after every batch and every epoch with the model and the
+// optimizer at hand, for logging, checkpointing or changing the learning rate,
+// and can stop training; `EarlyStopping` does so when a monitored value stops
+// improving. Grads can be clipped before each step; the batch stats carry
+// their norm from before clipping either way. A penalty over `Model::weights`
+// is added to every batch loss, not to the reported loss. Schedulers set the
+// learning rate before the first batch and step after the callbacks, on the
+// batch loss or the monitored epoch value.
+use crate::data::Dataset;
+use crate::nn::{mean_loss, Loss, Model};
+use crate::optim::{grad_norm, Clip, Grads, Optimizer};
+use crate::rand::Rng;
+use crate::regularize::Penalty;
+use crate::schedule::{Goal, Interval, Scheduler};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+// Scores a model on a dataset, e.g. its accuracy.
+pub type Metric<M> = fn(&M, &dyn Dataset) -> f64;
+
+pub type BatchCallback<'a, M, O> = Box<dyn FnMut(&mut M, &mut O, &BatchStats) -> Control + 'a>;
+pub type EpochCallback<'a, M, O> = Box<dyn FnMut(&mut M, &mut O, &EpochStats) -> Control + 'a>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Control {
+    Continue,
+    Stop,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchStats {
+    pub epoch: usize,
+    // counts from 1 within the epoch
+    pub batch: usize,
+    // counts from 1 over the whole run
+    pub step: usize,
+    pub loss: f64,
+    pub learning_rate: f64,
+    // before any clipping
+    pub grad_norm: f64,
+    // as handed to the optimizer, after any clipping
+    pub grads: Grads,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EpochStats {
+    pub epoch: usize,
+    // mean per-sample loss over the epoch's batches
+    pub loss: f64,
+    pub validation_loss: Option<f64>,
+    pub validation_metric: Option<f64>,
+    pub learning_rate: f64,
+}
+
+impl EpochStats {
+    // The value early stopping watches: the validation metric if there is one,
+    // else the validation loss, else the training loss.
+    pub fn monitored(&self) -> f64 {
+        self.validation_metric
+            .or(self.validation_loss)
+            .unwrap_or(self.loss)
+    }
+}
+
+// Stops once the monitored value has gone `patience` epochs without improving
+// on the best so far by more than `min_delta`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EarlyStopping {
+    pub patience: usize,
+    pub min_delta: f64,
+    pub goal: Goal,
+    best: Option<(usize, f64)>,
+    waited: usize,
+}
+
+impl EarlyStopping {
+    pub fn new(patience: usize, goal: Goal) -> Self {
+        EarlyStopping {
+            patience,
+            min_delta: 0.0,
+            goal,
+            best: None,
+            waited: 0,
+        }
+    }
+
+    pub fn update(&mut self, epoch: usize, value: f64) -> Control {
+        let improved = match (self.best, self.goal) {
+            (None, _) => true,
+            (Some((_, best)), Goal::Minimize) => value < best - self.min_delta,
+            (Some((_, best)), Goal::Maximize) => value > best + self.min_delta,
+        };
+        if improved {
+            self.best = Some((epoch, value));
+            self.waited = 0;
+            return Control::Continue;
+        }
+        self.waited += 1;
+        match self.waited >= self.patience {
+            true => Control::Stop,
+            false => Control::Continue,
+        }
+    }
+
+    // The best epoch and its value.
+    pub fn best(&self) -> Option<(usize, f64)> {
+        self.best
+    }
+}
+
+pub struct Trainer<'a, M, O> {
+    pub model: M,
+    pub optimizer: O,
+    loss: Loss,
+    epochs: usize,
+    batch_size: usize,
+    rng: Rng,
+    validation: Option<&'a dyn Dataset>,
+    metric: Option<Metric<M>>,
+    early_stopping: Option<EarlyStopping>,
+    clip: Option<Clip>,
+    penalty: Option<Penalty>,
+    on_batch_end: Vec<BatchCallback<'a, M, O>>,
+    on_epoch_end: Vec<EpochCallback<'a, M, O>>,
+    schedulers: Vec<(Box<dyn Scheduler + 'a>, Interval)>,
+}
+
+impl<'a, M: Model, O: Optimizer> Trainer<'a, M, O> {
+    // Ten epochs of batches of 32, shuffled with seed 0, until changed below.
+    pub fn new(model: M, optimizer: O, loss: Loss) -> Self {
+        Trainer {
+            model,
+            optimizer,
+            loss,
+            epochs: 10,
+            batch_size: 32,
+            rng: Rng::new(0),
+            validation: None,
+            metric: None,
+            early_stopping: None,
+            clip: None,
+            penalty: None,
+            on_batch_end: Vec::new(),
+            on_epoch_end: Vec::new(),
+            schedulers: Vec::new(),
+        }
+    }
+
+    pub fn epochs(mut self, epochs: usize) -> Self {
+        self.epochs = epochs;
+        self
+    }
+
+    // Zero is treated as one.
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.rng = Rng::new(seed);
+        self
+    }
+
+    // Evaluated after every epoch, without updating the model.
+    pub fn validation(mut self, dataset: &'a dyn Dataset) -> Self {
+        self.validation = Some(dataset);
+        self
+    }
+
+    // Scored on the validation set after every epoch.
+    pub fn metric(mut self, metric: Metric<M>) -> Self {
+        self.metric = Some(metric);
+        self
+    }
+
+    pub fn early_stopping(mut self, early_stopping: EarlyStopping) -> Self {
+        self.early_stopping = Some(early_stopping);
+        self
+    }
+
+    pub fn clip(mut self, clip: Clip) -> Self {
+        self.clip = Some(clip);
+        self
+    }
+
+    // Over `Model::weights` only, so an `Mlp` leaves its biases unpenalized.
+    pub fn penalty(mut self, penalty: Penalty) -> Self {
+        self.penalty = Some(penalty);
+        self
+    }
+
+    pub fn on_batch_end(
+        mut self,
+        callback: impl FnMut(&mut M, &mut O, &BatchStats) -> Control + 'a,
+    ) -> Self {
+        self.on_batch_end.push(Box::new(callback));
+        self
+    }
+
+    pub fn on_epoch_end(
+        mut self,
+        callback: impl FnMut(&mut M, &mut O, &EpochStats) -> Control + 'a,
+    ) -> Self {
+        self.on_epoch_end.push(Box::new(callback));
+        self
+    }
+
+    pub fn scheduler(mut self, scheduler: impl Scheduler + 'a, interval: Interval) -> Self {
+        self.schedulers.push((Box::new(scheduler), interval));
+        self
+    }
+
+    pub fn early_stopping_state(&self) -> Option<&EarlyStopping> {
+        self.early_stopping.as_ref()
+    }
+
+    // Runs up to `epochs` epochs over `train` and returns the stats of each.
+    // Calling it again continues from the current model.
+    pub fn fit<D: Dataset>(&mut self, train: &D) -> Vec<EpochStats> {
+        let mut history = Vec::new();
+        let mut step = 0;
+        for (scheduler, _) in self.schedulers.iter() {
+            scheduler.apply(&mut self.optimizer);
+        }
+        for epoch in 1..=self.epochs {
+            let (mut total, mut count) = (0.0, 0);
+            let mut stop = false;
+            let batches = train.shuffled_batches(self.batch_size, &mut self.rng);
+            for (index, batch) in batches.enumerate() {
+                let mut loss = mean_loss(&self.model, self.loss, &batch);
+                let data_loss = loss.value;
+                if let Some(penalty) = self.penalty {
+                    loss = loss + penalty.unit(&self.model.weights());
+                }
+                loss.grad = 1.0;
+                loss.traverse_backward();
+                let mut grads = loss.leaf_grads();
+                let grad_norm = match self.clip {
+                    Some(clip) => clip.apply(&mut grads),
+                    None => grad_norm(&grads),
+                };
+                self.optimizer.step(&mut self.model.params_mut(), &grads);
+                total += data_loss * batch.len() as f64;
+                count += batch.len();
+                step += 1;
+                let stats = BatchStats {
+                    epoch,
+                    batch: index + 1,
+                    step,
+                    loss: data_loss,
+                    learning_rate: self.optimizer.learning_rate(),
+                    grad_norm,
+                    grads,
+                };
+                for callback in self.on_batch_end.iter_mut() {
+                    stop |= callback(&mut self.model, &mut self.optimizer, &stats) == Control::Stop;
+                }
+                let optimizer = &mut self.optimizer;
+                step_schedulers(&mut self.schedulers, optimizer, Interval::Batch, stats.loss);
+                if stop {
+                    break;
+                }
+            }
+
+            let validation = self.validation;
+            let stats = EpochStats {
+                epoch,
+                loss: total / count.max(1) as f64,
+                validation_loss: validation.map(|data| self.evaluate(data)),
+                validation_metric: validation
+                    .zip(self.metric)
+                    .map(|(data, metric)| metric(&self.model, data)),
+                learning_rate: self.optimizer.learning_rate(),
+            };
+            for callback in self.on_epoch_end.iter_mut() {
+                stop |= callback(&mut self.model, &mut self.optimizer, &stats) == Control::Stop;
+            }
+            let monitored = stats.monitored();
+            step_schedulers(
+                &mut self.schedulers,
+                &mut self.optimizer,
+                Interval::Epoch,
+                monitored,
+            );
+            if let Some(early_stopping) = self.early_stopping.as_mut() {
+                stop |= early_stopping.update(epoch, monitored) == Control::Stop;
+            }
+            history.push(stats);
+            if stop {
+                break;
+            }
+        }
+        history
+    }
+
+    // Mean per-sample loss over `dataset`.
+    pub fn evaluate(&self, dataset: &dyn Dataset) -> f64 {
+        let total: f64 = (0..dataset.len())
+            .filter_map(|index| dataset.get(index))
+            .map(|(inputs, targets)| (self.loss)(self.model.forward(&inputs), &targets).value)
+            .sum();
+        total / dataset.len().max(1) as f64
+    }
+}
+
+// Takes the fields rather than the trainer, since batch schedulers step while
+// the epoch's batches still borrow its generator.
+fn step_schedulers<O: Optimizer>(
+    schedulers: &mut [(Box<dyn Scheduler + '_>, Interval)],
+    optimizer: &mut O,
+    interval: Interval,
+    monitored: f64,
+) {
+    for (scheduler, every) in schedulers.iter_mut() {
+        if *every == interval {
+            scheduler.step(monitored);
+            scheduler.apply(optimizer);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::InMemory;
+    use crate::generate;
+    use crate::nn::{squared_error, Activation, Mlp};
+    use crate::optim::Sgd;
+    use crate::schedule::{Constant, LinearWarmup, StepDecay};
+    use alloc::vec;
+    use libm::sqrt;
+
+    fn linear_model() -> Mlp {
+        Mlp::new(&[1, 1], Activation::Linear, 1)
+    }
+
+    fn sign_accuracy(mlp: &Mlp, dataset: &dyn Dataset) -> f64 {
+        let correct = (0..dataset.len())
+            .filter_map(|index| dataset.get(index))
+            .filter(|(inputs, targets)| (mlp.predict(inputs)[0] > 0.0) == (targets[0] > 0.0))
+            .count();
+        correct as f64 / dataset.len() as f64
+    }
+
+    #[test]
+    fn test_fits_a_line_in_batches() {
+        let data = generate::linear(40, &[0.8], -0.2, 0.0, 1);
+        let mut batches = Vec::new();
+        let mut trainer = Trainer::new(linear_model(), Sgd::new(0.3), squared_error)
+            .epochs(60)
+            .batch_size(16)
+            .on_batch_end(|_, _, stats| {
+                batches.push((stats.epoch, stats.batch, stats.step));
+                Control::Continue
+            });
+        let history = trainer.fit(&data);
+        assert_eq!(history.len(), 60);
+        assert!(history[59].loss < 1e-4);
+        assert_eq!(history[59].validation_loss, None);
+        let params = &trainer.model.layers[0].params;
+        assert!((params[0] - 0.8).abs() < 0.01 && (params[1] + 0.2).abs() < 0.01);
+        drop(trainer);
+        // 40 samples make batches of 16, 16 and 8
+        assert_eq!(batches.len(), 180);
+        assert_eq!(batches[2], (1, 3, 3));
+        assert_eq!(batches[179], (60, 3, 180));
+    }
+
+    #[test]
+    fn test_runs_are_reproducible() {
+        let data = generate::moons(20, 0.1, 2);
+        let run = |seed| {
+            let mut trainer = Trainer::new(
+                Mlp::new(&[2, 3, 1], Activation::Tanh, 1),
+                Sgd::new(0.1),
+                squared_error,
+            )
+            .epochs(3)
+            .batch_size(4)
+            .seed(seed);
+            trainer.fit(&data);
+            trainer.model
+        };
+        assert_eq!(run(5), run(5));
+        assert_ne!(run(5), run(6));
+    }
+
+    #[test]
+    fn test_validation_metric_and_callbacks() {
+        let train = generate::moons(30, 0.05, 1);
+        let validation = generate::moons(20, 0.05, 2);
+        let mut seen = Vec::new();
+        let mut trainer = Trainer::new(
+            Mlp::new(&[2, 4, 1], Activation::Tanh, 1),
+            Sgd::new(0.5),
+            squared_error,
+        )
+        .epochs(5)
+        .batch_size(10)
+        .validation(&validation)
+        .metric(sign_accuracy)
+        .on_epoch_end(|_, optimizer, stats| {
+            seen.push(stats.clone());
+            // halve the learning rate after every epoch
+            optimizer.set_learning_rate(optimizer.learning_rate() / 2.0);
+            match stats.epoch {
+                3 => Control::Stop,
+                _ => Control::Continue,
+            }
+        });
+        let history = trainer.fit(&train);
+        assert_eq!(history.len(), 3);
+        assert_eq!(trainer.optimizer.learning_rate, 0.0625);
+        drop(trainer);
+        assert_eq!(seen, history);
+        assert_eq!(history[1].learning_rate, 0.25);
+        let metric = history[2].validation_metric.unwrap();
+        assert!((0.0..=1.0).contains(&metric));
+        assert_eq!(history[2].monitored(), metric);
+        assert!(history[2].validation_loss.unwrap() > 0.0);
+    }
+
+    #[test]
+    fn test_clipping() {
+        // a single sample at x = 1 with target 10 from w = b = 0: the grads of
+        // the squared error are -20 each
+        let data = InMemory::new(vec![(vec![1.0], vec![10.0])]);
+        let mut model = linear_model();
+        model.layers[0].params = vec![0.0, 0.0];
+        let mut norms = Vec::new();
+        let mut trainer = Trainer::new(model, Sgd::new(0.1), squared_error)
+            .epochs(1)
+            .clip(Clip::Norm(sqrt(2.0)))
+            .on_batch_end(|_, _, stats| {
+                norms.push(stats.grad_norm);
+                Control::Continue
+            });
+        trainer.fit(&data);
+        // clipped to -1 each, then one step of 0.1
+        let params = trainer.model.layers[0].params.clone();
+        drop(trainer);
+        assert!((norms[0] - 20.0 * sqrt(2.0)).abs() < 1e-12);
+        assert!(params.iter().all(|p| (p - 0.1).abs() < 1e-12));
+
+        let mut model = linear_model();
+        model.layers[0].params = vec![0.0, 0.0];
+        let mut trainer = Trainer::new(model, Sgd::new(0.1), squared_error)
+            .epochs(1)
+            .clip(Clip::Value(0.5));
+        trainer.fit(&data);
+        assert!(trainer.model.layers[0]
+            .params
+            .iter()
+            .all(|p| (p - 0.05).abs() < 1e-12));
+    }
+
+    #[test]
+    fn test_penalty() {
+        // the data loss is already zero, so only the penalty moves the weight,
+        // and the bias isn't penalized
+        let data = InMemory::new(vec![(vec![0.0], vec![0.5])]);
+        let mut model = linear_model();
+        model.layers[0].params = vec![1.0, 0.5];
+        let mut trainer = Trainer::new(model, Sgd::new(0.1), squared_error)
+            .epochs(1)
+            .penalty(Penalty::L2(0.5));
+        let history = trainer.fit(&data);
+        assert_eq!(history[0].loss, 0.0);
+        assert_eq!(trainer.model.layers[0].params, [0.9, 0.5]);
+    }
+
+    #[test]
+    fn test_schedulers() {
+        let data = generate::linear(8, &[1.0], 0.0, 0.0, 1);
+        let mut trainer = Trainer::new(linear_model(), Sgd::new(1.0), squared_error)
+            .epochs(3)
+            .batch_size(4)
+            .scheduler(StepDecay::new(0.4, 1, 0.5), Interval::Epoch);
+        let rates: Vec<f64> = trainer.fit(&data).iter().map(|s| s.learning_rate).collect();
+        assert_eq!(rates, [0.4, 0.2, 0.1]);
+        assert_eq!(trainer.optimizer.learning_rate, 0.05);
+
+        let mut rates = Vec::new();
+        let mut trainer = Trainer::new(linear_model(), Sgd::new(1.0), squared_error)
+            .epochs(3)
+            .batch_size(4)
+            .scheduler(LinearWarmup::new(4, Constant(1.0)), Interval::Batch)
+            .on_batch_end(|_, _, stats| {
+                rates.push(stats.learning_rate);
+                Control::Continue
+            });
+        trainer.fit(&data);
+        drop(trainer);
+        assert_eq!(rates, [0.25, 0.5, 0.75, 1.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn test_early_stopping() {
+        let mut stopping = EarlyStopping::new(2, Goal::Minimize);
+        let controls: Vec<Control> = [1.0, 0.5, 0.6, 0.4, 0.4, 0.45]
+            .iter()
+            .enumerate()
+            .map(|(epoch, value)| stopping.update(epoch + 1, *value))
+            .collect();
+        assert_eq!(controls[..5], [Control::Continue; 5]);
+        assert_eq!(controls[5], Control::Stop);
+        assert_eq!(stopping.best(), Some((4, 0.4)));
+
+        let mut stopping = EarlyStopping::new(1, Goal::Maximize);
+        stopping.min_delta = 0.1;
+        assert_eq!(stopping.update(1, 0.5), Control::Continue);
+        assert_eq!(stopping.update(2, 0.55), Control::Stop);
+
+        // nothing left to learn: a constant target the model fits immediately
+        let data = InMemory::new(vec![(vec![0.0], vec![0.0]); 4]);
+        let mut model = linear_model();
+        model.layers[0].params = vec![0.0, 0.0];
+        let mut trainer = Trainer::new(model, Sgd::new(0.1), squared_error)
+            .epochs(50)
+            .early_stopping(EarlyStopping::new(3, Goal::Minimize));
+        assert_eq!(trainer.fit(&data).len(), 4);
+        assert_eq!(
+            trainer.early_stopping_state().unwrap().best(),
+            Some((1, 0.0))
+        );
+        assert_eq!(trainer.evaluate(&data), 0.0);
+    }
+}
@@ -0,0 +1,306 @@
+// Layered (Sugiyama-style) placement of a traced graph.
+//
+// Nodes are assigned to layers by their distance from the root, which sits in
+// the rightmost column, with inputs further left. Edges spanning more than one
+// layer are split by dummy vertices, so every edge only connects neighbouring
+// columns and never runs through a box. The order within each layer is then
+// improved with a few barycenter sweeps to cut down on edge crossings, and
+// every edge is routed as horizontal and vertical segments through the gap
+// between two columns.
+use alloc::vec;
+use alloc::vec::Vec;
+use ratatui::layout::Rect;
+
+// Box size and the gaps between boxes, in terminal cells.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Spacing {
+    pub node: (u16, u16),
+    pub gap: (u16, u16),
+}
+
+// (child, parent, corner points) of a routed edge
+pub type Route = (usize, usize, Vec<(u16, u16)>);
+
+#[derive(Debug, PartialEq)]
+pub struct Placement {
+    // outer rect of every node, `None` for nodes that are not visible
+    pub rects: Vec<Option<Rect>>,
+    // every visible edge
+    pub edges: Vec<Route>,
+    pub size: (u16, u16),
+}
+
+const SWEEPS: usize = 4;
+
+// A vertex is either one of the nodes or a dummy standing in for an edge as it
+// crosses a layer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Vertex {
+    Node(usize),
+    Dummy,
+}
+
+// `children[n]` lists the inputs of node `n` without repeats; node 0 is the root.
+// `levels` are the depths found while tracing, which may put a child shared by
+// several parents too close to the root; children are pushed down below every
+// parent. Only nodes reachable from the root through `children` are placed.
+pub fn place(children: &[Vec<usize>], levels: &[usize], spacing: Spacing) -> Placement {
+    let count = children.len();
+    if count == 0 {
+        return Placement {
+            rects: Vec::new(),
+            edges: Vec::new(),
+            size: (0, 0),
+        };
+    }
+
+    // parents before children
+    let order = reverse_postorder(children);
+    let mut layers: Vec<usize> = levels.to_vec();
+    for parent in order.iter() {
+        for child in children[*parent].iter() {
+            layers[*child] = layers[*child].max(layers[*parent] + 1);
+        }
+    }
+    let depth = order.iter().map(|idx| layers[*idx]).max().unwrap_or(0);
+
+    // vertices, their layers, and the edges between neighbouring layers; nodes
+    // come first in trace order, which is the initial order within each layer
+    let mut reachable = vec![false; count];
+    for idx in order.iter() {
+        reachable[*idx] = true;
+    }
+    let mut vertices: Vec<Vertex> = Vec::new();
+    let mut vertex_layers: Vec<usize> = Vec::new();
+    let mut vertex_of = vec![usize::MAX; count];
+    for idx in (0..count).filter(|idx| reachable[*idx]) {
+        vertex_of[idx] = vertices.len();
+        vertices.push(Vertex::Node(idx));
+        vertex_layers.push(layers[idx]);
+    }
+    let mut links: Vec<(usize, usize)> = Vec::new();
+    let mut chains: Vec<(usize, usize, Vec<usize>)> = Vec::new();
+    for parent in order.iter() {
+        for child in children[*parent].iter() {
+            let mut chain = vec![vertex_of[*parent]];
+            for layer in layers[*parent] + 1..layers[*child] {
+                vertices.push(Vertex::Dummy);
+                vertex_layers.push(layer);
+                chain.push(vertices.len() - 1);
+            }
+            chain.push(vertex_of[*child]);
+            for pair in chain.windows(2) {
+                links.push((pair[0], pair[1]));
+            }
+            chains.push((*child, *parent, chain));
+        }
+    }
+
+    let mut rows: Vec<Vec<usize>> = vec![Vec::new(); depth + 1];
+    for (vertex, layer) in vertex_layers.iter().enumerate() {
+        rows[*layer].push(vertex);
+    }
+    order_by_barycenter(&mut rows, &vertex_layers, &links);
+
+    // one column per layer, the root's on the right; each column is centered
+    let pitch = (
+        spacing.node.0 + spacing.gap.0,
+        spacing.node.1 + spacing.gap.1,
+    );
+    let tallest = rows.iter().map(Vec::len).max().unwrap_or(0) as u16;
+    let mut origins = vec![(0u16, 0u16); vertices.len()];
+    for (layer, row) in rows.iter().enumerate() {
+        let x = ((depth - layer) as u16).saturating_mul(pitch.0);
+        let top = (tallest - row.len() as u16).saturating_mul(pitch.1) / 2;
+        for (position, vertex) in row.iter().enumerate() {
+            origins[*vertex] = (
+                x,
+                top.saturating_add((position as u16).saturating_mul(pitch.1)),
+            );
+        }
+    }
+
+    let mut rects = vec![None; count];
+    for (vertex, kind) in vertices.iter().enumerate() {
+        if let Vertex::Node(idx) = kind {
+            let (x, y) = origins[vertex];
+            rects[*idx] = Some(Rect::new(x, y, spacing.node.0, spacing.node.1));
+        }
+    }
+
+    let middle = spacing.node.1 / 2;
+    let edges = chains
+        .into_iter()
+        .map(|(child, parent, chain)| {
+            let parent_rank = rows[layers[parent]]
+                .iter()
+                .position(|vertex| *vertex == vertex_of[parent])
+                .unwrap_or(0) as u16;
+            // spread the vertical runs of different parents over the gap
+            let lanes = spacing.gap.0.saturating_sub(2).max(1);
+            let lane = 1 + parent_rank % lanes;
+            let mut points = Vec::new();
+            // walk from the child towards the parent, left to right
+            for pair in chain.windows(2).rev() {
+                let (from, to) = (origins[pair[1]], origins[pair[0]]);
+                let start = (from.0 + spacing.node.0, from.1 + middle);
+                let end_y = to.1 + middle;
+                let turn = to.0.saturating_sub(spacing.gap.0) + lane;
+                points.push(start);
+                points.push((turn, start.1));
+                points.push((turn, end_y));
+                match vertices[pair[0]] {
+                    // stop next to the box border
+                    Vertex::Node(_) => points.push((to.0.saturating_sub(1), end_y)),
+                    // run through the dummy's whole slot
+                    Vertex::Dummy => points.push((to.0 + spacing.node.0, end_y)),
+                }
+            }
+            points.dedup();
+            (child, parent, points)
+        })
+        .collect();
+
+    Placement {
+        rects,
+        edges,
+        size: (
+            (depth as u16 + 1).saturating_mul(pitch.0),
+            tallest.saturating_mul(pitch.1),
+        ),
+    }
+}
+
+fn reverse_postorder(children: &[Vec<usize>]) -> Vec<usize> {
+    fn visit(idx: usize, children: &[Vec<usize>], visited: &mut [bool], order: &mut Vec<usize>) {
+        if visited[idx] {
+            return;
+        }
+        visited[idx] = true;
+        for child in children[idx].iter() {
+            visit(*child, children, visited, order);
+        }
+        order.push(idx);
+    }
+    let mut visited = vec![false; children.len()];
+    let mut order = Vec::new();
+    visit(0, children, &mut visited, &mut order);
+    order.reverse();
+    order
+}
+
+// Alternately sorts every layer by the mean position of its neighbours in the
+// layer above and in the layer below.
+fn order_by_barycenter(rows: &mut [Vec<usize>], layers: &[usize], links: &[(usize, usize)]) {
+    let mut position = vec![0usize; layers.len()];
+    let index = |rows: &[Vec<usize>], position: &mut [usize]| {
+        for row in rows.iter() {
+            for (idx, vertex) in row.iter().enumerate() {
+                position[*vertex] = idx;
+            }
+        }
+    };
+    index(rows, &mut position);
+    for sweep in 0..SWEEPS {
+        let downwards = sweep % 2 == 0;
+        let layer_order: Vec<usize> = if downwards {
+            (1..rows.len()).collect()
+        } else {
+            (0..rows.len().saturating_sub(1)).rev().collect()
+        };
+        for layer in layer_order {
+            let barycenter = |vertex: usize| {
+                let neighbours: Vec<usize> = links
+                    .iter()
+                    .filter_map(|(upper, lower)| match downwards {
+                        true if *lower == vertex => Some(position[*upper]),
+                        false if *upper == vertex => Some(position[*lower]),
+                        _ => None,
+                    })
+                    .collect();
+                if neighbours.is_empty() {
+                    position[vertex] as f64
+                } else {
+                    neighbours.iter().sum::<usize>() as f64 / neighbours.len() as f64
+                }
+            };
+            let mut keyed: Vec<(f64, usize)> = rows[layer]
+                .iter()
+                .map(|vertex| (barycenter(*vertex), *vertex))
+                .collect();
+            // stable, so ties keep their current order
+            keyed.sort_by(|a, b| a.0.total_cmp(&b.0));
+            rows[layer] = keyed.into_iter().map(|(_, vertex)| vertex).collect();
+            index(rows, &mut position);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPACING: Spacing = Spacing {
+        node: (4, 3),
+        gap: (6, 1),
+    };
+
+    #[test]
+    fn test_layers_become_columns() {
+        // 0 <- 1 <- 2, 0 <- 3
+        let children = vec![vec![1, 3], vec![2], vec![], vec![]];
+        let placement = place(&children, &[0, 1, 2, 1], SPACING);
+        let rects: Vec<Rect> = placement.rects.iter().map(|rect| rect.unwrap()).collect();
+        assert_eq!(rects[0].x, 20);
+        assert_eq!(rects[1].x, 10);
+        assert_eq!(rects[3].x, 10);
+        assert_eq!(rects[2].x, 0);
+        assert_eq!(placement.size, (30, 8));
+        for a in 0..rects.len() {
+            for b in a + 1..rects.len() {
+                assert!(!rects[a].intersects(rects[b]), "{} and {} overlap", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_shared_children_are_pushed_below_every_parent() {
+        // node 2 is an input of both the root and node 1, and was traced at level 1
+        let children = vec![vec![1, 2], vec![2], vec![]];
+        let placement = place(&children, &[0, 1, 1], SPACING);
+        let x = |idx: usize| placement.rects[idx].unwrap().x;
+        assert!(x(2) < x(1) && x(1) < x(0));
+        // the edge from 2 to the root passes a dummy in the middle column
+        let (_, _, points) = placement
+            .edges
+            .iter()
+            .find(|(child, parent, _)| (*child, *parent) == (2, 0))
+            .unwrap();
+        assert_eq!(points.first().unwrap().0, x(2) + 4);
+        assert_eq!(points.last().unwrap().0, x(0) - 1);
+        // every segment is horizontal or vertical
+        for pair in points.windows(2) {
+            assert!(pair[0].0 == pair[1].0 || pair[0].1 == pair[1].1);
+        }
+    }
+
+    #[test]
+    fn test_unreachable_nodes_are_not_placed() {
+        let children = vec![vec![1], vec![], vec![]];
+        let placement = place(&children, &[0, 1, 1], SPACING);
+        assert!(placement.rects[1].is_some());
+        assert_eq!(placement.rects[2], None);
+        assert_eq!(placement.edges.len(), 1);
+    }
+
+    #[test]
+    fn test_barycenter_removes_crossings() {
+        // the root's inputs are 1 and 2, but 2's input comes first in trace
+        // order, which would cross without reordering
+        let children = vec![vec![1, 2], vec![4], vec![3], vec![], vec![]];
+        let placement = place(&children, &[0, 1, 1, 2, 2], SPACING);
+        assert!(placement.rects[1].unwrap().y < placement.rects[2].unwrap().y);
+        let y = |idx: usize| placement.rects[idx].unwrap().y;
+        assert_eq!(y(1) < y(2), y(4) < y(3));
+    }
+}
@@ -0,0 +1,1175 @@
+// Terminal graph explorer and headless renderers, behind the `debug` feature.
+// `show` takes over the terminal until the user quits, so any application can
+// pop up a graph while debugging.
+mod layout;
+pub mod snapshot;
+
+use crate::core::{Op, Unit};
+use crate::trace::{trace, TracedEdge, TracedNode};
+use alloc::borrow::ToOwned;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use layout::{Placement, Spacing};
+use ratatui::{
+    backend::{Backend, CrosstermBackend},
+    buffer::Buffer,
+    layout::{Constraint, Direction, Layout, Position},
+    prelude::Rect,
+    style::{Color, Modifier, Style},
+    symbols::line,
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Paragraph, Widget},
+    Frame, Terminal,
+};
+
+use crossterm::{
+    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use std::io;
+use tui_nodes::NodeLayout;
+
+const NODE_SIZE: (u16, u16) = (14, 5);
+const BOXES: Spacing = Spacing {
+    node: NODE_SIZE,
+    gap: (6, 1),
+};
+const DOTS: Spacing = Spacing {
+    node: (1, 1),
+    gap: (4, 0),
+};
+// Digits after the decimal point in node boxes, changed with +/-. At most as
+// many as fit inside a box after "v -0.".
+const DEFAULT_PRECISION: usize = 4;
+const MAX_PRECISION: usize = NODE_SIZE.0 as usize - 2 - "v -0.".len();
+const HELP: &str =
+    "←→↑↓ move  Enter details  hjkl pan  f/b animate  +/- digits  c/C collapse  z zoom  q quit";
+
+pub fn show(root: &Unit) -> Result<(), io::Error> {
+    let mut terminal = setup_terminal()?;
+    let result = explore(&mut terminal, root);
+    // restore the terminal even if drawing or reading events failed
+    restore_terminal(&mut terminal)?;
+    result
+}
+
+fn explore<B: Backend>(terminal: &mut Terminal<B>, root: &Unit) -> Result<(), io::Error> {
+    let (nodes, edges) = trace(root);
+    let mut explorer = Explorer::new(&nodes, &edges);
+    loop {
+        terminal.draw(|f| draw(f, &nodes, &mut explorer))?;
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press && explorer.handle_key(key.code) {
+                return Ok(());
+            }
+        }
+    }
+}
+
+// What a node box displays. While stepping through a pass this differs from
+// the unit: values not computed yet are `None` and grads build up from zero.
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct Shown {
+    value: Option<f64>,
+    grad: f64,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Pass {
+    Forward,
+    Backward,
+}
+
+// Full node boxes, or one dot per node to see the shape of large graphs.
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Zoom {
+    Boxes,
+    Dots,
+}
+
+struct Animation {
+    pass: Pass,
+    // op nodes in the order the pass visits them
+    order: Vec<usize>,
+    // how many nodes of `order` have been processed
+    step: usize,
+}
+
+// The whole graph drawn off screen with nothing selected, e.g. for snapshots.
+// Graphs past the u16::MAX cells of a buffer are cut off at the bottom.
+pub fn render_graph(root: &Unit) -> Buffer {
+    let (nodes, edges) = trace(root);
+    let mut explorer = Explorer::new(&nodes, &edges);
+    // no node has this index, so none is highlighted
+    explorer.selected = nodes.len();
+    render_canvas(&nodes, &mut explorer, (u16::MAX, u16::MAX))
+}
+
+// Selection, detail pane and viewport of the explorer, kept apart from drawing
+// so that navigation can be tested without a terminal.
+struct Explorer {
+    selected: usize,
+    details: bool,
+    precision: usize,
+    // top-left corner of the viewport on the graph canvas
+    offset: (u16, u16),
+    children: Vec<Vec<usize>>,
+    parents: Vec<Option<usize>>,
+    levels: Vec<usize>,
+    // inputs of every node in `prev` order, so `a * a` lists `a` twice
+    inputs: Vec<Vec<usize>>,
+    local_grads: Vec<Vec<f64>>,
+    units: Vec<Shown>,
+    shown: Vec<Shown>,
+    animation: Option<Animation>,
+    // collapsed nodes hide everything only reachable through them
+    collapsed: Vec<bool>,
+    // activations applied to a computed value, i.e. the output of a neuron
+    neurons: Vec<bool>,
+    zoom: Zoom,
+    // inner rect of every node on the canvas, filled in while drawing
+    zones: Vec<Rect>,
+    // size of the viewport, filled in while drawing
+    viewport: (u16, u16),
+}
+
+impl Explorer {
+    fn new(nodes: &[TracedNode], edges: &[TracedEdge]) -> Self {
+        let mut children = vec![Vec::new(); nodes.len()];
+        let mut parents = vec![None; nodes.len()];
+        for (from, to) in edges.iter() {
+            let from = index_of(nodes, from);
+            let to = index_of(nodes, to);
+            if !children[to].contains(&from) {
+                children[to].push(from);
+            }
+            parents[from].get_or_insert(to);
+        }
+        let units: Vec<Shown> = nodes
+            .iter()
+            .map(|(node, _, _)| Shown {
+                value: Some(node.value),
+                grad: node.grad,
+            })
+            .collect();
+        Explorer {
+            selected: 0,
+            details: false,
+            precision: DEFAULT_PRECISION,
+            offset: (0, 0),
+            children,
+            parents,
+            levels: nodes.iter().map(|(_, _, level)| *level).collect(),
+            inputs: nodes
+                .iter()
+                .map(|(node, _, _)| node.prev.iter().map(|p| index_of(nodes, p)).collect())
+                .collect(),
+            local_grads: nodes
+                .iter()
+                .map(|(node, _, _)| node.local_grads().to_vec())
+                .collect(),
+            shown: units.clone(),
+            units,
+            animation: None,
+            collapsed: vec![false; nodes.len()],
+            neurons: nodes
+                .iter()
+                .map(|(node, _, _)| {
+                    matches!(node.op, Some(Op::Tanh(_) | Op::Sigmoid(_) | Op::Relu(_)))
+                        && node.prev.iter().any(|input| input.op.is_some())
+                })
+                .collect(),
+            zoom: Zoom::Boxes,
+            zones: Vec::new(),
+            viewport: (0, 0),
+        }
+    }
+
+    // Returns true when the explorer should quit.
+    fn handle_key(&mut self, code: KeyCode) -> bool {
+        if self.animation.is_some() {
+            match code {
+                KeyCode::Char('n') | KeyCode::Char(' ') => return self.step(true),
+                KeyCode::Char('p') | KeyCode::Backspace => return self.step(false),
+                KeyCode::Esc => {
+                    self.animation = None;
+                    self.shown = self.units.clone();
+                    return false;
+                }
+                _ => {}
+            }
+        }
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => return true,
+            KeyCode::Char('f') => self.animate(Pass::Forward),
+            KeyCode::Char('b') => self.animate(Pass::Backward),
+            KeyCode::Enter => self.details = !self.details,
+            KeyCode::Char('+') => self.precision = (self.precision + 1).min(MAX_PRECISION),
+            KeyCode::Char('-') => self.precision = self.precision.saturating_sub(1),
+            KeyCode::Char('c') if !self.children[self.selected].is_empty() => {
+                self.collapsed[self.selected] = !self.collapsed[self.selected];
+            }
+            KeyCode::Char('C') => self.toggle_neurons(),
+            KeyCode::Char('z') => {
+                self.zoom = match self.zoom {
+                    Zoom::Boxes => Zoom::Dots,
+                    Zoom::Dots => Zoom::Boxes,
+                }
+            }
+            KeyCode::Left => {
+                if let Some(child) = self.visible_children()[self.selected].first() {
+                    self.select(*child);
+                }
+            }
+            KeyCode::Right => {
+                if let Some(parent) = self.parents[self.selected] {
+                    self.select(parent);
+                }
+            }
+            KeyCode::Up => self.select_sibling(false),
+            KeyCode::Down => self.select_sibling(true),
+            KeyCode::Char('h') => self.offset.0 = self.offset.0.saturating_sub(NODE_SIZE.0),
+            KeyCode::Char('l') => self.offset.0 = self.offset.0.saturating_add(NODE_SIZE.0),
+            KeyCode::Char('k') => self.offset.1 = self.offset.1.saturating_sub(NODE_SIZE.1),
+            KeyCode::Char('j') => self.offset.1 = self.offset.1.saturating_add(NODE_SIZE.1),
+            _ => {}
+        }
+        false
+    }
+
+    fn animate(&mut self, pass: Pass) {
+        let mut order = self.topological_order();
+        order.retain(|idx| !self.inputs[*idx].is_empty());
+        if pass == Pass::Backward {
+            order.reverse();
+        }
+        self.animation = Some(Animation {
+            pass,
+            order,
+            step: 0,
+        });
+        self.replay();
+        // start from the root the backward pass is seeded at
+        if pass == Pass::Backward {
+            self.select(0);
+        }
+    }
+
+    fn step(&mut self, forward: bool) -> bool {
+        if let Some(animation) = self.animation.as_mut() {
+            if forward && animation.step < animation.order.len() {
+                animation.step += 1;
+            } else if !forward && animation.step > 0 {
+                animation.step -= 1;
+            }
+        }
+        self.replay();
+        false
+    }
+
+    // Recomputes what every node shows after the steps taken so far, and
+    // highlights the node processed last.
+    fn replay(&mut self) {
+        let Some(animation) = self.animation.as_ref() else {
+            return;
+        };
+        let done = &animation.order[..animation.step];
+        match animation.pass {
+            Pass::Forward => {
+                for (idx, shown) in self.shown.iter_mut().enumerate() {
+                    *shown = Shown {
+                        value: self.inputs[idx]
+                            .is_empty()
+                            .then_some(self.units[idx].value)
+                            .flatten(),
+                        grad: 0.0,
+                    };
+                }
+                for idx in done {
+                    self.shown[*idx].value = self.units[*idx].value;
+                }
+            }
+            Pass::Backward => {
+                for (idx, shown) in self.shown.iter_mut().enumerate() {
+                    *shown = Shown {
+                        value: self.units[idx].value,
+                        grad: 0.0,
+                    };
+                }
+                self.shown[0].grad = 1.0;
+                for idx in done {
+                    let grad = self.shown[*idx].grad;
+                    for (input, local) in
+                        self.inputs[*idx].iter().zip(self.local_grads[*idx].iter())
+                    {
+                        self.shown[*input].grad += local * grad;
+                    }
+                }
+            }
+        }
+        if let Some(last) = done.last().copied() {
+            self.select(last);
+        }
+    }
+
+    // Children before parents, starting from the root at index 0.
+    fn topological_order(&self) -> Vec<usize> {
+        fn visit(idx: usize, inputs: &[Vec<usize>], visited: &mut [bool], order: &mut Vec<usize>) {
+            if visited[idx] {
+                return;
+            }
+            visited[idx] = true;
+            for input in inputs[idx].iter() {
+                visit(*input, inputs, visited, order);
+            }
+            order.push(idx);
+        }
+        let mut visited = vec![false; self.inputs.len()];
+        let mut order = Vec::new();
+        if !self.inputs.is_empty() {
+            visit(0, &self.inputs, &mut visited, &mut order);
+        }
+        order
+    }
+
+    fn status(&self) -> String {
+        match self.animation.as_ref() {
+            Some(animation) => format!(
+                "{} pass, step {}/{}  n/Space next  p previous  Esc stop  q quit",
+                match animation.pass {
+                    Pass::Forward => "forward",
+                    Pass::Backward => "backward",
+                },
+                animation.step,
+                animation.order.len()
+            ),
+            None => HELP.to_owned(),
+        }
+    }
+
+    // Collapses every neuron into one box, or expands everything again.
+    fn toggle_neurons(&mut self) {
+        if self.collapsed.iter().any(|collapsed| *collapsed) {
+            self.collapsed
+                .iter_mut()
+                .for_each(|collapsed| *collapsed = false);
+        } else {
+            self.collapsed.clone_from(&self.neurons);
+        }
+        // the selection may have disappeared into a collapsed neuron
+        if !self.visible()[self.selected] {
+            self.select(0);
+        }
+    }
+
+    // Children of every node, with those of collapsed nodes left out.
+    fn visible_children(&self) -> Vec<Vec<usize>> {
+        self.children
+            .iter()
+            .zip(self.collapsed.iter())
+            .map(|(children, collapsed)| {
+                if *collapsed {
+                    Vec::new()
+                } else {
+                    children.clone()
+                }
+            })
+            .collect()
+    }
+
+    fn visible(&self) -> Vec<bool> {
+        let children = self.visible_children();
+        let mut visible = vec![false; children.len()];
+        let mut stack = vec![0];
+        while let Some(idx) = stack.pop() {
+            if idx < visible.len() && !visible[idx] {
+                visible[idx] = true;
+                stack.extend(children[idx].iter().copied());
+            }
+        }
+        visible
+    }
+
+    fn spacing(&self) -> Spacing {
+        match self.zoom {
+            Zoom::Boxes => BOXES,
+            Zoom::Dots => DOTS,
+        }
+    }
+
+    // Moves to the previous or next node on the same level, wrapping around.
+    fn select_sibling(&mut self, forward: bool) {
+        let level = self.levels[self.selected];
+        let visible = self.visible();
+        let same_level: Vec<usize> = (0..self.levels.len())
+            .filter(|idx| visible[*idx] && self.levels[*idx] == level)
+            .collect();
+        let position = same_level
+            .iter()
+            .position(|idx| *idx == self.selected)
+            .unwrap();
+        let next = if forward {
+            (position + 1) % same_level.len()
+        } else {
+            (position + same_level.len() - 1) % same_level.len()
+        };
+        self.select(same_level[next]);
+    }
+
+    fn select(&mut self, idx: usize) {
+        self.selected = idx;
+        self.scroll_to_selected();
+    }
+
+    // Pans the viewport just enough to bring the selected node into view.
+    fn scroll_to_selected(&mut self) {
+        let Some(zone) = self.zones.get(self.selected).copied() else {
+            return;
+        };
+        if zone.width == 0 || self.viewport.0 == 0 || self.viewport.1 == 0 {
+            return;
+        }
+        // the zone is the inner rect, so widen it by the border
+        let (left, top) = (zone.x.saturating_sub(1), zone.y.saturating_sub(1));
+        let (right, bottom) = (zone.right() + 1, zone.bottom() + 1);
+        if left < self.offset.0 {
+            self.offset.0 = left;
+        } else if right > self.offset.0 + self.viewport.0 {
+            self.offset.0 = right - self.viewport.0;
+        }
+        if top < self.offset.1 {
+            self.offset.1 = top;
+        } else if bottom > self.offset.1 + self.viewport.1 {
+            self.offset.1 = bottom - self.viewport.1;
+        }
+    }
+}
+
+fn index_of(nodes: &[TracedNode], unit: &Unit) -> usize {
+    nodes.iter().position(|(node, _, _)| *node == unit).unwrap()
+}
+
+fn draw(f: &mut Frame, nodes: &[TracedNode], explorer: &mut Explorer) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(f.area());
+    let details_width = if explorer.details { 32 } else { 0 };
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Min(1), Constraint::Length(details_width)])
+        .split(rows[0]);
+    let graph_area = columns[0];
+
+    explorer.viewport = (graph_area.width, graph_area.height);
+    let canvas = render_canvas(nodes, explorer, explorer.viewport);
+    blit(&canvas, graph_area, f.buffer_mut());
+
+    if explorer.details {
+        let selected = explorer.selected;
+        f.render_widget(
+            details(nodes[selected].0, explorer.shown[selected]),
+            columns[1],
+        );
+    }
+    f.render_widget(
+        Paragraph::new(explorer.status()).style(Style::default().add_modifier(Modifier::REVERSED)),
+        rows[1],
+    );
+}
+
+// Renders what a `viewport`-sized window at the explorer's offset shows of the
+// graph, after keeping the offset inside the graph. Zones are filled in for
+// every node, drawn or not, so that any of them can be scrolled to.
+fn render_canvas(nodes: &[TracedNode], explorer: &mut Explorer, viewport: (u16, u16)) -> Buffer {
+    // a forward pass has not computed any grads yet
+    let show_grads = !explorer
+        .animation
+        .as_ref()
+        .is_some_and(|animation| animation.pass == Pass::Forward);
+    let node_metadata =
+        generate_node_metadata(nodes, &explorer.shown, show_grads, explorer.precision);
+    let titles: Vec<String> = nodes
+        .iter()
+        .zip(explorer.collapsed.iter())
+        .map(|((node, _, _), collapsed)| match collapsed {
+            true => format!("{} [+]", node.label),
+            false => node.label.to_owned(),
+        })
+        .collect();
+    let node_layouts = create_node_layouts(nodes, &titles, explorer.selected);
+
+    let placement = layout::place(
+        &explorer.visible_children(),
+        &explorer.levels,
+        explorer.spacing(),
+    );
+    let (width, height) = placement.size;
+    let offset = (
+        explorer.offset.0.min(width.saturating_sub(viewport.0)),
+        explorer.offset.1.min(height.saturating_sub(viewport.1)),
+    );
+    explorer.offset = offset;
+    let size = (
+        viewport.0.min(width - offset.0),
+        viewport.1.min(height - offset.1),
+    );
+    let mut canvas = Buffer::empty(canvas_area(offset, size));
+    let view = canvas.area;
+    draw_edges(nodes, &placement, &mut canvas);
+
+    let mut zones = vec![Rect::default(); nodes.len()];
+    for (idx, rect) in placement.rects.iter().enumerate() {
+        let Some(rect) = *rect else {
+            continue;
+        };
+        if explorer.zoom == Zoom::Dots {
+            zones[idx] = rect;
+            if !view.intersects(rect) {
+                continue;
+            }
+            let style = match idx == explorer.selected {
+                true => Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+                false => node_style(nodes[idx].0).1,
+            };
+            let dot = if explorer.collapsed[idx] {
+                "◆"
+            } else {
+                "●"
+            };
+            canvas[(rect.x, rect.y)].set_symbol(dot).set_style(style);
+            continue;
+        }
+        let block = node_layouts[idx].block();
+        let inner = block.inner(rect);
+        zones[idx] = inner;
+        if !view.intersects(rect) {
+            continue;
+        }
+        // drawn whole on its own, then copied, so a box cut off by the edge of
+        // the view doesn't get a border there
+        let mut node = Buffer::empty(rect);
+        block.render(rect, &mut node);
+        let (symbol, value, grad) = &node_metadata[idx];
+        let grad_style = if show_grads {
+            gradient_style(explorer.shown[idx].grad)
+        } else {
+            Style::default().fg(Color::DarkGray)
+        };
+        let text = vec![
+            Line::from(Span::styled(symbol.clone(), node_style(nodes[idx].0).1)),
+            Line::from(value.clone()),
+            Line::from(Span::styled(grad.clone(), grad_style)),
+        ];
+        Paragraph::new(text).render(inner, &mut node);
+        for position in rect.positions() {
+            if view.contains(position) {
+                canvas[position] = node[position].clone();
+            }
+        }
+    }
+    explorer.zones = zones;
+    canvas
+}
+
+const UP: u8 = 1;
+const DOWN: u8 = 2;
+const LEFT: u8 = 4;
+const RIGHT: u8 = 8;
+
+// The window at `(x, y)`, cut at the bottom to the u16::MAX cells of a buffer.
+fn canvas_area((x, y): (u16, u16), (width, height): (u16, u16)) -> Rect {
+    let height = height.min(u16::MAX / width.max(1));
+    Rect {
+        x,
+        y,
+        width,
+        height,
+    }
+}
+
+// Draws the part of every routed edge inside the canvas with the line style of
+// the op it feeds into, joining lines that meet or cross into the matching
+// box-drawing character.
+fn draw_edges(nodes: &[TracedNode], placement: &Placement, canvas: &mut Buffer) {
+    let area = canvas.area;
+    let mut cells = vec![(0u8, line::NORMAL); area.area() as usize];
+    let mut mark = |(x, y): (u16, u16), direction: u8, set: line::Set| {
+        if area.contains(Position::new(x, y)) {
+            let (x, y) = ((x - area.x) as usize, (y - area.y) as usize);
+            let cell = &mut cells[y * area.width as usize + x];
+            *cell = (cell.0 | direction, set);
+        }
+    };
+    for (_, parent, points) in placement.edges.iter() {
+        let set = match node_style(nodes[*parent].0).0 {
+            BorderType::Thick => line::THICK,
+            BorderType::Double => line::DOUBLE,
+            BorderType::Rounded => line::ROUNDED,
+            _ => line::NORMAL,
+        };
+        let (Some(first), Some(last)) = (points.first(), points.last()) else {
+            continue;
+        };
+        // the ends touch the boxes on either side
+        mark(*first, LEFT, set);
+        mark(*last, RIGHT, set);
+        for pair in points.windows(2) {
+            let ((x1, y1), (x2, y2)) = (pair[0], pair[1]);
+            if y1 == y2 {
+                let (from, to) = (x1.min(x2), x1.max(x2));
+                for x in from..=to {
+                    let left = if x > from { LEFT } else { 0 };
+                    let right = if x < to { RIGHT } else { 0 };
+                    mark((x, y1), left | right, set);
+                }
+            } else {
+                let (from, to) = (y1.min(y2), y1.max(y2));
+                for y in from..=to {
+                    let up = if y > from { UP } else { 0 };
+                    let down = if y < to { DOWN } else { 0 };
+                    mark((x1, y), up | down, set);
+                }
+            }
+        }
+    }
+    for (idx, (directions, set)) in cells.into_iter().enumerate() {
+        let symbol = match directions {
+            0 => continue,
+            d if d == UP | DOWN || d == UP || d == DOWN => set.vertical,
+            d if d == LEFT | RIGHT || d == LEFT || d == RIGHT => set.horizontal,
+            d if d == DOWN | RIGHT => set.top_left,
+            d if d == DOWN | LEFT => set.top_right,
+            d if d == UP | RIGHT => set.bottom_left,
+            d if d == UP | LEFT => set.bottom_right,
+            d if d == UP | DOWN | LEFT => set.vertical_left,
+            d if d == UP | DOWN | RIGHT => set.vertical_right,
+            d if d == LEFT | RIGHT | DOWN => set.horizontal_down,
+            d if d == LEFT | RIGHT | UP => set.horizontal_up,
+            _ => set.cross,
+        };
+        let (x, y) = (idx % area.width as usize, idx / area.width as usize);
+        canvas[(area.x + x as u16, area.y + y as u16)].set_symbol(symbol);
+    }
+}
+
+// Copies `canvas` into the top-left of `area` in `buf`.
+fn blit(canvas: &Buffer, area: Rect, buf: &mut Buffer) {
+    let source = canvas.area;
+    for y in 0..area.height.min(source.height) {
+        for x in 0..area.width.min(source.width) {
+            buf[(area.x + x, area.y + y)] = canvas[(source.x + x, source.y + y)].clone();
+        }
+    }
+}
+
+fn details(node: &Unit, shown: Shown) -> Paragraph<'static> {
+    let op = node.op.as_ref().map_or("leaf", |op| op.symbol());
+    let text = format!(
+        "label:  {}\nvalue:  {}\ngrad:   {}\nop:     {}\ninputs: {}",
+        node.label,
+        format_value(shown.value),
+        shown.grad,
+        op,
+        node.prev.len()
+    );
+    Paragraph::new(text).block(
+        Block::default()
+            .title("details")
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded),
+    )
+}
+
+fn format_value(value: Option<f64>) -> String {
+    value.map_or_else(|| "?".to_owned(), |value| value.to_string())
+}
+
+// Symbol, value and grad lines of every node box.
+fn generate_node_metadata(
+    nodes: &[TracedNode],
+    shown: &[Shown],
+    show_grads: bool,
+    precision: usize,
+) -> Vec<(String, String, String)> {
+    nodes
+        .iter()
+        .zip(shown.iter())
+        .map(|((node, _, _), shown)| {
+            let symbol = match node.op.as_ref() {
+                Some(op) => match curve(op) {
+                    Some(curve) => format!("{} {}", op.symbol(), curve),
+                    None => op.symbol().to_owned(),
+                },
+                None if node.is_constant() => "const".to_owned(),
+                None => "leaf".to_owned(),
+            };
+            let value = match shown.value {
+                Some(value) => format!("v {:.*}", precision, value),
+                None => "v ?".to_owned(),
+            };
+            let grad = if show_grads {
+                format!("g {:.*}", precision, shown.grad)
+            } else {
+                "g ?".to_owned()
+            };
+            (symbol, value, grad)
+        })
+        .collect()
+}
+
+// Border and accent colour of a node box; every op kind looks different.
+fn node_style(node: &Unit) -> (BorderType, Style) {
+    let (border, color) = match node.op {
+        Some(Op::Add(_)) => (BorderType::Thick, Color::Blue),
+        Some(Op::Mul(_)) => (BorderType::Double, Color::Magenta),
+        Some(Op::Tanh(_)) => (BorderType::Rounded, Color::Cyan),
+        Some(Op::Sigmoid(_)) => (BorderType::Rounded, Color::LightMagenta),
+        Some(Op::Relu(_)) => (BorderType::Rounded, Color::LightBlue),
+        None if node.is_constant() => (BorderType::Plain, Color::DarkGray),
+        None => (BorderType::Plain, Color::Reset),
+    };
+    (border, Style::default().fg(color))
+}
+
+// A sketch of each activation's curve next to its name, as the three share a
+// border and only the colour sets them apart otherwise.
+fn curve(op: &Op) -> Option<&'static str> {
+    match op {
+        Op::Tanh(_) => Some("∿"),
+        Op::Sigmoid(_) => Some("∫"),
+        Op::Relu(_) => Some("_/"),
+        Op::Add(_) | Op::Mul(_) => None,
+    }
+}
+
+// Green for positive grads and red for negative ones, bold when large and dim
+// when close to zero.
+fn gradient_style(grad: f64) -> Style {
+    let magnitude = grad.abs();
+    if grad == 0.0 || !grad.is_finite() {
+        return Style::default().fg(Color::DarkGray);
+    }
+    let style = Style::default().fg(if grad > 0.0 { Color::Green } else { Color::Red });
+    if magnitude >= 1.0 {
+        style.add_modifier(Modifier::BOLD)
+    } else if magnitude < 0.01 {
+        style.add_modifier(Modifier::DIM)
+    } else {
+        style
+    }
+}
+
+fn create_node_layouts<'a>(
+    nodes: &[TracedNode],
+    titles: &'a [String],
+    selected: usize,
+) -> Vec<NodeLayout<'a>> {
+    nodes
+        .iter()
+        .zip(titles.iter())
+        .enumerate()
+        .map(|(index, ((node, _, _), title))| {
+            let (border, style) = node_style(node);
+            let layout = NodeLayout::new(NODE_SIZE)
+                .with_title(title)
+                .with_border_type(border);
+
+            if index == selected {
+                layout.with_border_style(
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                )
+            } else {
+                layout.with_border_style(style)
+            }
+        })
+        .collect()
+}
+
+pub fn setup_terminal() -> Result<Terminal<CrosstermBackend<std::io::Stdout>>, io::Error> {
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    let backend = CrosstermBackend::new(stdout);
+    let terminal = Terminal::new(backend)?;
+    Ok(terminal)
+}
+
+pub fn restore_terminal(
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+) -> Result<(), io::Error> {
+    disable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
+        LeaveAlternateScreen,
+        DisableMouseCapture
+    )?;
+    terminal.show_cursor()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn demo_tree() -> Unit {
+        let mut ab = Unit::new(2.0f64, "a") + Unit::new(3.0f64, "b");
+        ab.label = "ab";
+        let mut root = ab * Unit::new(4.0f64, "c");
+        root.label = "root";
+        root
+    }
+
+    #[test]
+    fn test_explorer_navigation() {
+        let root = demo_tree();
+        let (nodes, edges) = trace(&root);
+        let mut explorer = Explorer::new(&nodes, &edges);
+        let label = |explorer: &Explorer| nodes[explorer.selected].0.label;
+
+        assert_eq!(label(&explorer), "root");
+        explorer.handle_key(KeyCode::Right); // the root has no parent
+        assert_eq!(label(&explorer), "root");
+        explorer.handle_key(KeyCode::Left);
+        assert_eq!(label(&explorer), "ab");
+        explorer.handle_key(KeyCode::Down);
+        assert_eq!(label(&explorer), "c");
+        explorer.handle_key(KeyCode::Down); // wraps around the level
+        assert_eq!(label(&explorer), "ab");
+        explorer.handle_key(KeyCode::Up);
+        assert_eq!(label(&explorer), "c");
+        explorer.handle_key(KeyCode::Left); // leaves have no children
+        assert_eq!(label(&explorer), "c");
+        explorer.handle_key(KeyCode::Right);
+        assert_eq!(label(&explorer), "root");
+    }
+
+    #[test]
+    fn test_explorer_details_and_quit() {
+        let root = demo_tree();
+        let (nodes, edges) = trace(&root);
+        let mut explorer = Explorer::new(&nodes, &edges);
+        assert!(!explorer.handle_key(KeyCode::Enter));
+        assert!(explorer.details);
+        assert!(!explorer.handle_key(KeyCode::Enter));
+        assert!(!explorer.details);
+        assert!(explorer.handle_key(KeyCode::Char('q')));
+        assert!(explorer.handle_key(KeyCode::Esc));
+    }
+
+    #[test]
+    fn test_explorer_scrolls_to_selection() {
+        let root = demo_tree();
+        let (nodes, edges) = trace(&root);
+        let mut explorer = Explorer::new(&nodes, &edges);
+        // a viewport only wide enough for one node
+        let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(14, 12)).unwrap();
+        terminal.draw(|f| draw(f, &nodes, &mut explorer)).unwrap();
+        let root_zone = explorer.zones[0];
+        assert!(root_zone.x >= explorer.offset.0);
+
+        explorer.handle_key(KeyCode::Left);
+        let child_zone = explorer.zones[explorer.selected];
+        // the node and its border are inside the viewport
+        assert!(child_zone.x > explorer.offset.0);
+        assert!(child_zone.right() < explorer.offset.0 + explorer.viewport.0);
+
+        explorer.handle_key(KeyCode::Char('h'));
+        explorer.handle_key(KeyCode::Char('h'));
+        explorer.handle_key(KeyCode::Char('h'));
+        assert_eq!(explorer.offset.0, 0);
+    }
+
+    #[test]
+    fn test_explorer_steps_forward() {
+        let root = demo_tree();
+        let (nodes, edges) = trace(&root);
+        let mut explorer = Explorer::new(&nodes, &edges);
+        let value = |explorer: &Explorer, label: &str| {
+            let idx = nodes.iter().position(|(n, _, _)| n.label == label).unwrap();
+            explorer.shown[idx].value
+        };
+
+        explorer.handle_key(KeyCode::Char('f'));
+        assert_eq!(value(&explorer, "a"), Some(2.0));
+        assert_eq!(value(&explorer, "ab"), None);
+        assert_eq!(value(&explorer, "root"), None);
+        explorer.handle_key(KeyCode::Char('n'));
+        assert_eq!(value(&explorer, "ab"), Some(5.0));
+        assert_eq!(nodes[explorer.selected].0.label, "ab");
+        explorer.handle_key(KeyCode::Char(' '));
+        assert_eq!(value(&explorer, "root"), Some(20.0));
+        explorer.handle_key(KeyCode::Char('n')); // already at the last step
+        assert_eq!(
+            explorer.status(),
+            "forward pass, step 2/2  n/Space next  p previous  Esc stop  q quit"
+        );
+        explorer.handle_key(KeyCode::Char('p'));
+        assert_eq!(value(&explorer, "root"), None);
+
+        // Esc leaves the animation instead of quitting
+        assert!(!explorer.handle_key(KeyCode::Esc));
+        assert!(explorer.animation.is_none());
+        assert_eq!(value(&explorer, "root"), Some(20.0));
+        assert!(explorer.handle_key(KeyCode::Esc));
+    }
+
+    #[test]
+    fn test_explorer_steps_backward() {
+        let a = Unit::new(3.0f64, "a");
+        let mut root = (a.clone() * a + Unit::new(4.0f64, "b")) * Unit::new(-2.0f64, "c");
+        root.grad = 1.0;
+        root.traverse_backward();
+        let (nodes, edges) = trace(&root);
+        let mut explorer = Explorer::new(&nodes, &edges);
+        let grad = |explorer: &Explorer, label: &str| {
+            let idx = nodes.iter().position(|(n, _, _)| n.label == label).unwrap();
+            explorer.shown[idx].grad
+        };
+
+        explorer.handle_key(KeyCode::Char('b'));
+        assert_eq!(grad(&explorer, "result"), 1.0);
+        assert_eq!(grad(&explorer, "c"), 0.0);
+        explorer.handle_key(KeyCode::Char('n'));
+        assert_eq!(grad(&explorer, "c"), 9.0 + 4.0);
+        for _ in 0..2 {
+            explorer.handle_key(KeyCode::Char('n'));
+        }
+        // `a` appears twice in `a * a`, so both paths add up in its box
+        assert_eq!(grad(&explorer, "a"), 2.0 * 3.0 * -2.0);
+        assert_eq!(grad(&explorer, "b"), root.leaf_grads()["b"]);
+        assert_eq!(grad(&explorer, "c"), root.leaf_grads()["c"]);
+    }
+
+    #[test]
+    fn test_draw_shows_details_and_help() {
+        let root = demo_tree();
+        let (nodes, edges) = trace(&root);
+        let mut explorer = Explorer::new(&nodes, &edges);
+        explorer.handle_key(KeyCode::Enter);
+        let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(100, 20)).unwrap();
+        terminal.draw(|f| draw(f, &nodes, &mut explorer)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let text: String = buffer.content().iter().map(|cell| cell.symbol()).collect();
+        assert!(text.contains("label:  root"));
+        assert!(text.contains("value:  20"));
+        assert!(text.contains("q quit"));
+    }
+
+    #[test]
+    fn test_node_metadata_covers_every_op() {
+        let x = Unit::new(0.5f64, "x");
+        let sigmoid = Unit::with_child(
+            0.25,
+            [x.clone()].into_iter().collect(),
+            Op::Sigmoid('s'),
+            "s",
+        );
+        let relu = Unit::with_child(0.0, [x.clone()].into_iter().collect(), Op::Relu('r'), "r");
+        let mut root = (x.tanh() + sigmoid) * (relu + Unit::constant(2.0));
+        root.grad = 1.0;
+        root.traverse_backward();
+        let (nodes, edges) = trace(&root);
+        let explorer = Explorer::new(&nodes, &edges);
+        let metadata = generate_node_metadata(&nodes, &explorer.shown, true, 2);
+        let symbols: Vec<&str> = metadata
+            .iter()
+            .map(|(symbol, _, _)| symbol.as_str())
+            .collect();
+        for symbol in ["*", "+", "tanh ∿", "leaf", "sigmoid ∫", "relu _/", "const"] {
+            assert!(symbols.contains(&symbol), "missing {}", symbol);
+        }
+        assert_eq!(
+            metadata[0],
+            ("*".to_owned(), "v 1.42".to_owned(), "g 1.00".to_owned())
+        );
+        let hidden = generate_node_metadata(&nodes, &explorer.shown, false, 0);
+        assert_eq!(hidden[0].1, "v 1");
+        assert_eq!(hidden[0].2, "g ?");
+
+        // five op kinds, five looks
+        let mut styles: Vec<(BorderType, Style)> = Vec::new();
+        for (node, _, _) in nodes.iter().filter(|(node, _, _)| node.op.is_some()) {
+            if !styles.contains(&node_style(node)) {
+                styles.push(node_style(node));
+            }
+        }
+        assert_eq!(styles.len(), 5);
+    }
+
+    #[test]
+    fn test_gradient_style() {
+        assert_eq!(gradient_style(0.0).fg, Some(Color::DarkGray));
+        assert_eq!(gradient_style(f64::NAN).fg, Some(Color::DarkGray));
+        assert_eq!(gradient_style(0.5), Style::default().fg(Color::Green));
+        assert_eq!(gradient_style(-0.5), Style::default().fg(Color::Red));
+        assert!(gradient_style(-3.0).add_modifier.contains(Modifier::BOLD));
+        assert!(gradient_style(0.001).add_modifier.contains(Modifier::DIM));
+    }
+
+    #[test]
+    fn test_explorer_precision() {
+        let root = demo_tree();
+        let (nodes, edges) = trace(&root);
+        let mut explorer = Explorer::new(&nodes, &edges);
+        assert_eq!(explorer.precision, DEFAULT_PRECISION);
+        explorer.handle_key(KeyCode::Char('+'));
+        assert_eq!(explorer.precision, DEFAULT_PRECISION + 1);
+        for _ in 0..20 {
+            explorer.handle_key(KeyCode::Char('-'));
+        }
+        assert_eq!(explorer.precision, 0);
+        for _ in 0..20 {
+            explorer.handle_key(KeyCode::Char('+'));
+        }
+        assert_eq!(explorer.precision, MAX_PRECISION);
+        // a negative value below 1 at full precision fills the box exactly
+        let value = format!("v {:.*}", MAX_PRECISION, -0.123456789);
+        assert_eq!(value.len(), NODE_SIZE.0 as usize - 2);
+    }
+
+    #[test]
+    fn test_explorer_collapse_and_zoom() {
+        let root = demo_tree();
+        let (nodes, edges) = trace(&root);
+        let mut explorer = Explorer::new(&nodes, &edges);
+        let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(100, 20)).unwrap();
+        let ab = nodes.iter().position(|(n, _, _)| n.label == "ab").unwrap();
+
+        explorer.handle_key(KeyCode::Left);
+        assert_eq!(explorer.selected, ab);
+        explorer.handle_key(KeyCode::Char('c'));
+        assert!(explorer.collapsed[ab]);
+        terminal.draw(|f| draw(f, &nodes, &mut explorer)).unwrap();
+        let text: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(text.contains("ab [+]"));
+        assert!(!text.contains("a────"));
+        // the inputs of a collapsed node are neither drawn nor reachable
+        let a = nodes.iter().position(|(n, _, _)| n.label == "a").unwrap();
+        assert_eq!(explorer.zones[a], Rect::default());
+        explorer.handle_key(KeyCode::Left);
+        assert_eq!(explorer.selected, ab);
+
+        explorer.handle_key(KeyCode::Char('z'));
+        assert_eq!(explorer.zoom, Zoom::Dots);
+        terminal.draw(|f| draw(f, &nodes, &mut explorer)).unwrap();
+        assert_eq!(explorer.zones[0].width, 1);
+        assert_eq!(explorer.zones[ab].width, 1);
+    }
+
+    #[test]
+    fn test_collapse_neurons() {
+        let x = Unit::new(1.0f64, "x");
+        let w = Unit::new(0.5f64, "w");
+        let neuron = (x * w + Unit::new(0.1f64, "b")).tanh();
+        let root = neuron.clone() * neuron.tanh();
+        let (nodes, edges) = trace(&root);
+        let mut explorer = Explorer::new(&nodes, &edges);
+        // both tanh nodes sit on top of computed values
+        assert_eq!(explorer.neurons.iter().filter(|n| **n).count(), 2);
+        explorer.handle_key(KeyCode::Char('C'));
+        let visible = explorer.visible();
+        assert_eq!(visible.iter().filter(|v| **v).count(), 3);
+        explorer.handle_key(KeyCode::Char('C'));
+        assert!(explorer.visible().iter().all(|v| *v));
+    }
+
+    #[test]
+    fn test_edges_join_into_box_drawing() {
+        let root = demo_tree();
+        let (nodes, edges) = trace(&root);
+        let explorer = Explorer::new(&nodes, &edges);
+        let placement = layout::place(&explorer.children, &explorer.levels, BOXES);
+        let mut canvas = Buffer::empty(Rect::new(0, 0, placement.size.0, placement.size.1));
+        draw_edges(&nodes, &placement, &mut canvas);
+        let text: String = canvas.content().iter().map(|cell| cell.symbol()).collect();
+        // `a` is level with `ab` and `b` joins it from below in a thick junction;
+        // `ab` and `c` meet halfway in a double one in front of the root
+        assert!(text.contains('┳'));
+        assert!(text.contains('╠'));
+    }
+
+    fn neuron() -> Unit {
+        let x1 = Unit::new(2.0f64, "x1");
+        let w1 = Unit::new(-3.0f64, "w1");
+        let x2 = Unit::new(0.0f64, "x2");
+        let w2 = Unit::new(1.0f64, "w2");
+        let n = x1.clone() * w1 + x2 * w2 + Unit::new(6.88f64, "b");
+        let mut root = n.tanh() * x1;
+        root.grad = 1.0;
+        root.traverse_backward();
+        root
+    }
+
+    // `testdata/neuron_graph.txt` is the text snapshot of `neuron()` after backward;
+    // regenerate it when the drawing changes on purpose.
+    #[test]
+    fn test_graph_snapshot_is_up_to_date() {
+        let text = snapshot::to_text(&render_graph(&neuron()));
+        assert_eq!(text, include_str!("../testdata/neuron_graph.txt"));
+    }
+
+    // Selects `idx` after a first draw has placed every node, then draws again
+    // and says whether the highlighted box is on screen.
+    fn scroll_to(nodes: &[TracedNode], explorer: &mut Explorer, idx: usize) -> bool {
+        let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(80, 24)).unwrap();
+        terminal.draw(|f| draw(f, nodes, explorer)).unwrap();
+        explorer.select(idx);
+        terminal.draw(|f| draw(f, nodes, explorer)).unwrap();
+        let buffer = terminal.backend().buffer();
+        buffer.content().iter().any(|cell| cell.fg == Color::Yellow)
+    }
+
+    #[test]
+    fn test_render_large_graph() {
+        // a balanced sum tree far taller than a buffer can hold at once
+        let mut level: Vec<Unit> = (0..128).map(|i| Unit::new(i as f64, "x")).collect();
+        while level.len() > 1 {
+            level = level
+                .chunks(2)
+                .map(|pair| pair[0].clone() + pair[1].clone())
+                .collect();
+        }
+        let (nodes, edges) = trace(&level[0]);
+        let mut explorer = Explorer::new(&nodes, &edges);
+        assert!(scroll_to(&nodes, &mut explorer, 0));
+        // the lowest node of the last layer is drawn once scrolled to
+        let last = (0..nodes.len())
+            .max_by_key(|idx| (explorer.levels[*idx], explorer.zones[*idx].y))
+            .unwrap();
+        assert!(scroll_to(&nodes, &mut explorer, last));
+        let zone = explorer.zones[last];
+        assert!(zone.y > explorer.offset.1);
+        assert!(zone.bottom() < explorer.offset.1 + explorer.viewport.1);
+
+        // and so are both ends of a chain hundreds of layers deep
+        let mut chain = Unit::new(1.0f64, "x");
+        for i in 0..300 {
+            chain = chain + Unit::new(i as f64, "c");
+        }
+        let (nodes, edges) = trace(&chain);
+        let mut explorer = Explorer::new(&nodes, &edges);
+        assert!(scroll_to(&nodes, &mut explorer, nodes.len() - 1));
+        assert!(scroll_to(&nodes, &mut explorer, 0));
+    }
+
+    #[test]
+    fn test_render_graph_highlights_nothing() {
+        let buffer = render_graph(&neuron());
+        assert!(buffer.content().iter().all(|cell| cell.fg != Color::Yellow));
+        let svg = snapshot::to_svg(&buffer);
+        assert!(svg.contains(">╭tanh────────╮</text>"));
+    }
+}
@@ -0,0 +1,276 @@
+// Turns a rendered ratatui buffer into plain text, ANSI-coloured text or SVG,
+// so graphs can be checked against golden files and embedded in reports
+// without a terminal.
+use alloc::borrow::ToOwned;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Write;
+use ratatui::buffer::{Buffer, Cell};
+use ratatui::style::{Color, Modifier, Style};
+
+// Size of one terminal cell in SVG user units.
+const CELL_WIDTH: usize = 9;
+const CELL_HEIGHT: usize = 18;
+const FONT_SIZE: usize = 15;
+const BACKGROUND: &str = "#1e1e1e";
+const FOREGROUND: &str = "#d4d4d4";
+
+// One line per row with trailing blanks removed.
+pub fn to_text(buffer: &Buffer) -> String {
+    let mut out = String::new();
+    for y in 0..buffer.area.height {
+        let line: String = (0..buffer.area.width)
+            .map(|x| buffer[(buffer.area.x + x, buffer.area.y + y)].symbol())
+            .collect();
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+// Like `to_text`, with SGR escapes wherever the style changes.
+pub fn to_ansi(buffer: &Buffer) -> String {
+    let mut out = String::new();
+    for y in 0..buffer.area.height {
+        let mut current = Style::default();
+        for x in 0..buffer.area.width {
+            let cell = &buffer[(buffer.area.x + x, buffer.area.y + y)];
+            let style = style_of(cell);
+            if style != current {
+                out.push_str(&sgr(style));
+                current = style;
+            }
+            out.push_str(cell.symbol());
+        }
+        out.push_str("\x1b[0m\n");
+    }
+    out
+}
+
+// The cell's style with `Reset` colours left unset, so that blank cells compare
+// equal to `Style::default()`.
+fn style_of(cell: &Cell) -> Style {
+    let set = |color: Color| (color != Color::Reset).then_some(color);
+    Style {
+        fg: set(cell.fg),
+        bg: set(cell.bg),
+        add_modifier: cell.modifier,
+        ..Style::default()
+    }
+}
+
+fn sgr(style: Style) -> String {
+    let mut codes = vec![String::from("0")];
+    for (modifier, code) in [
+        (Modifier::BOLD, "1"),
+        (Modifier::DIM, "2"),
+        (Modifier::ITALIC, "3"),
+        (Modifier::UNDERLINED, "4"),
+        (Modifier::REVERSED, "7"),
+    ] {
+        if style.add_modifier.contains(modifier) {
+            codes.push(code.to_owned());
+        }
+    }
+    if let Some(code) = style.fg.and_then(|color| color_code(color, false)) {
+        codes.push(code);
+    }
+    if let Some(code) = style.bg.and_then(|color| color_code(color, true)) {
+        codes.push(code);
+    }
+    format!("\x1b[{}m", codes.join(";"))
+}
+
+fn color_code(color: Color, background: bool) -> Option<String> {
+    let base = match color {
+        Color::Reset => return None,
+        Color::Indexed(index) => {
+            return Some(format!("{};5;{}", if background { 48 } else { 38 }, index))
+        }
+        Color::Rgb(r, g, b) => {
+            return Some(format!(
+                "{};2;{};{};{}",
+                if background { 48 } else { 38 },
+                r,
+                g,
+                b
+            ))
+        }
+        Color::Black => 30,
+        Color::Red => 31,
+        Color::Green => 32,
+        Color::Yellow => 33,
+        Color::Blue => 34,
+        Color::Magenta => 35,
+        Color::Cyan => 36,
+        Color::Gray => 37,
+        Color::DarkGray => 90,
+        Color::LightRed => 91,
+        Color::LightGreen => 92,
+        Color::LightYellow => 93,
+        Color::LightBlue => 94,
+        Color::LightMagenta => 95,
+        Color::LightCyan => 96,
+        Color::White => 97,
+    };
+    Some((base + if background { 10 } else { 0 }).to_string())
+}
+
+// A standalone SVG drawing every cell on a grid, with the buffer's colours on a
+// dark background. Runs of cells sharing a style become one `<text>` stretched
+// to the width of its cells, so box-drawing characters line up.
+pub fn to_svg(buffer: &Buffer) -> String {
+    let (width, height) = (
+        buffer.area.width as usize * CELL_WIDTH,
+        buffer.area.height as usize * CELL_HEIGHT,
+    );
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">",
+        width, height, width, height
+    );
+    let _ = writeln!(
+        out,
+        "<rect width=\"100%\" height=\"100%\" fill=\"{}\"/>",
+        BACKGROUND
+    );
+    let _ = writeln!(
+        out,
+        "<g font-family=\"monospace\" font-size=\"{}\" fill=\"{}\">",
+        FONT_SIZE, FOREGROUND
+    );
+    for y in 0..buffer.area.height {
+        let cells: Vec<(&str, Style)> = (0..buffer.area.width)
+            .map(|x| {
+                let cell = &buffer[(buffer.area.x + x, buffer.area.y + y)];
+                (cell.symbol(), style_of(cell))
+            })
+            .collect();
+        let top = y as usize * CELL_HEIGHT;
+        for (x, (_, style)) in cells.iter().enumerate() {
+            if let Some(fill) = style.bg.and_then(hex) {
+                let _ = writeln!(
+                    out,
+                    "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>",
+                    x * CELL_WIDTH,
+                    top,
+                    CELL_WIDTH,
+                    CELL_HEIGHT,
+                    fill
+                );
+            }
+        }
+        let mut start = 0;
+        while start < cells.len() {
+            let style = cells[start].1;
+            let end = (start..cells.len())
+                .find(|x| !same_text_style(cells[*x].1, style))
+                .unwrap_or(cells.len());
+            let run: String = cells[start..end]
+                .iter()
+                .map(|(symbol, _)| *symbol)
+                .collect();
+            if !run.trim().is_empty() {
+                let _ = write!(
+                    out,
+                    "<text x=\"{}\" y=\"{}\" textLength=\"{}\" lengthAdjust=\"spacingAndGlyphs\" xml:space=\"preserve\"",
+                    start * CELL_WIDTH,
+                    top + CELL_HEIGHT * 3 / 4,
+                    (end - start) * CELL_WIDTH
+                );
+                if let Some(fill) = style.fg.and_then(hex) {
+                    let _ = write!(out, " fill=\"{}\"", fill);
+                }
+                if style.add_modifier.contains(Modifier::BOLD) {
+                    out.push_str(" font-weight=\"bold\"");
+                }
+                if style.add_modifier.contains(Modifier::DIM) {
+                    out.push_str(" opacity=\"0.6\"");
+                }
+                let _ = writeln!(out, ">{}</text>", escape(&run));
+            }
+            start = end;
+        }
+    }
+    out.push_str("</g>\n</svg>\n");
+    out
+}
+
+fn same_text_style(a: Style, b: Style) -> bool {
+    a.fg == b.fg && a.add_modifier == b.add_modifier
+}
+
+// The xterm defaults for the named colours.
+fn hex(color: Color) -> Option<String> {
+    let (r, g, b) = match color {
+        Color::Reset => return None,
+        Color::Rgb(r, g, b) => (r, g, b),
+        Color::Black => (0, 0, 0),
+        Color::Red => (205, 0, 0),
+        Color::Green => (0, 205, 0),
+        Color::Yellow => (205, 205, 0),
+        Color::Blue => (0, 0, 238),
+        Color::Magenta => (205, 0, 205),
+        Color::Cyan => (0, 205, 205),
+        Color::Gray => (229, 229, 229),
+        Color::DarkGray => (127, 127, 127),
+        Color::LightRed => (255, 0, 0),
+        Color::LightGreen => (0, 255, 0),
+        Color::LightYellow => (255, 255, 0),
+        Color::LightBlue => (92, 92, 255),
+        Color::LightMagenta => (255, 0, 255),
+        Color::LightCyan => (0, 255, 255),
+        Color::White => (255, 255, 255),
+        // the 256-colour palette is rarely used here; fall back to the text colour
+        Color::Indexed(_) => return None,
+    };
+    Some(format!("#{:02x}{:02x}{:02x}", r, g, b))
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::layout::Rect;
+
+    fn sample() -> Buffer {
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 6, 2));
+        buffer.set_string(0, 0, "a<b", Style::default().fg(Color::Red));
+        buffer.set_string(0, 1, "ok", Style::default().add_modifier(Modifier::BOLD));
+        buffer
+    }
+
+    #[test]
+    fn test_to_text_trims_rows() {
+        assert_eq!(to_text(&sample()), "a<b\nok\n");
+    }
+
+    #[test]
+    fn test_to_ansi_switches_styles() {
+        assert_eq!(
+            to_ansi(&sample()),
+            "\x1b[0;31ma<b\x1b[0m   \x1b[0m\n\x1b[0;1mok\x1b[0m    \x1b[0m\n"
+        );
+    }
+
+    #[test]
+    fn test_to_svg() {
+        let svg = to_svg(&sample());
+        assert!(
+            svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"54\" height=\"36\"")
+        );
+        assert!(svg.contains("fill=\"#cd0000\">a&lt;b</text>"));
+        assert!(svg.contains("font-weight=\"bold\">ok</text>"));
+        // blank runs are left out
+        assert_eq!(svg.matches("<text").count(), 2);
+        assert!(svg.ends_with("</svg>\n"));
+    }
+}